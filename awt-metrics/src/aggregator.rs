@@ -1,27 +1,111 @@
+use alloc::borrow::Cow;
 use alloc::format;
 use core::fmt::{Debug, Display, Formatter, Result};
-use std::collections::HashMap;
+use core::time::Duration;
+use std::collections::BTreeMap;
+use thiserror::Error;
 
 use awt_simulation::request::Data as RequestData;
+use awt_simulation::server::Stats as ServerStats;
+use awt_simulation::Simulation;
 
-use crate::{Metric, MetricType};
+use crate::{Metric, MetricType, Value};
 
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum AggregatorError {
+    #[error("No metrics are configured, statistics will be empty")]
+    NoMetrics,
+    #[error("Statistics have already been calculated")]
+    AlreadyCalculated,
+    #[error("Value cannot be merged into {0}")]
+    MismatchedValue(MetricType),
+    #[error("Run was evaluated with different metrics")]
+    MismatchedRun,
+}
+
+/// The values of an `Aggregator`'s metrics for a single simulation, without the metrics
+/// themselves. Evaluated with `Aggregator::evaluate`, e.g. in parallel for many simulations, then
+/// loaded into the `Aggregator` with `Aggregator::load` to report them.
+#[derive(Debug, Clone, Default)]
+pub struct Run {
+    simulation: usize,
+    seed: Option<u64>,
+    // The value and sample count of each metric, in name order
+    values: Vec<(Value, usize)>,
+}
+
+impl Run {
+    pub fn set_simulation(&mut self, id: usize) {
+        self.simulation = id;
+    }
+
+    #[must_use]
+    pub fn simulation(&self) -> usize {
+        self.simulation
+    }
+
+    /// Sets the rng seed the simulation was run with, so that it can be replayed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// The rng seed the simulation was run with, if set.
+    #[must_use]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+/// Calculates `Metric`s for a single simulation.
+///
+/// An `Aggregator` is configured with `with_metrics`, then cloned for each simulation and
+/// calculated once with `calculate`. To reuse an `Aggregator` for another simulation, `clean` it
+/// first. `reset` and `recalculate` keep the simulation id and seed, to recalculate the same one.
+///
+/// For many simulations, `evaluate` each into a `Run` instead, which borrows the `Aggregator`
+/// rather than cloning it, then `load` each `Run` in turn to report it.
 #[derive(Default, Clone)]
 pub struct Aggregator {
-    metrics: HashMap<MetricType, Metric>,
+    metrics: BTreeMap<String, Metric>,
     simulation: usize,
+    seed: Option<u64>,
+    calculated: bool,
 }
 
+/// A header line with the simulation id and any seed, then a table with a row for each `Metric` in
+/// name order:
+///
+/// ```text
+/// Statistics for simulation_id: 0, seed: 42
+/// Metric                         Target       Value        On Target Samples
+/// ServiceLevel(20s)              0.8          0.75         false     40
+/// ```
+///
+/// Names longer than their column push the rest of the row along. Values which have not been
+/// reported are shown as `None`. This format is stable, so that it can be parsed by other tools.
 impl Display for Aggregator {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result {
-        writeln!(f, "Statistics for simluation_id: {}", self.simulation)?;
+        write!(f, "Statistics for simulation_id: {}", self.simulation)?;
+        if let Some(seed) = self.seed {
+            write!(f, ", seed: {seed}")?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:30} {:12} {:12} On Target Samples",
+            "Metric", "Target", "Value"
+        )?;
+
         for metric in self.metrics.values() {
             writeln!(
                 f,
-                "{:20} {:<5} {}",
-                format!("{:?}", metric.metric()),
+                "{:30} {:12} {:12} {:9} {}",
+                metric.name(),
+                metric.target().to_string(),
+                metric.value().to_string(),
                 metric.on_target(),
-                metric
+                metric.sample_count()
             )?;
         }
         Ok(())
@@ -40,28 +124,895 @@ impl Debug for Aggregator {
 impl Aggregator {
     #[must_use]
     pub fn with_metrics(metrics: &[Metric]) -> Self {
-        let metrics = metrics.iter().map(|m| (m.metric(), m.clone())).collect();
-        Self {
-            metrics,
+        let mut aggregator = Self {
+            metrics: BTreeMap::new(),
             simulation: usize::default(),
+            seed: None,
+            calculated: false,
+        };
+        for metric in metrics {
+            aggregator.push(metric.clone());
         }
+        aggregator
     }
 
     pub fn set_simulation(&mut self, id: usize) {
         self.simulation = id;
     }
 
-    pub fn clean(&mut self) {}
+    #[must_use]
+    pub fn simulation(&self) -> usize {
+        self.simulation
+    }
+
+    /// Sets the rng seed the simulation was run with, so that it can be replayed.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// The rng seed the simulation was run with, if set.
+    #[must_use]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Clears all calculated values along with the simulation id and seed, keeping the configured
+    /// metrics and their targets. This is the way to reuse an `Aggregator` for sequential
+    /// simulations, rather than cloning one per simulation.
+    pub fn clean(&mut self) {
+        self.reset();
+        self.simulation = usize::default();
+        self.seed = None;
+    }
+
+    /// Returns whether every `Metric` in this `Aggregator` is on target.
+    #[must_use]
+    pub fn all_on_target(&self) -> bool {
+        self.metrics.values().all(Metric::on_target)
+    }
+
+    /// The total `score` of every `Metric` in this `Aggregator`.
+    #[must_use]
+    pub fn score(&self) -> f64 {
+        self.metrics.values().map(Metric::score).sum()
+    }
+
+    /// Returns an iterator over the `Metric`s in this `Aggregator`, in name order.
+    pub fn metrics(&self) -> impl Iterator<Item = &Metric> {
+        self.metrics.values()
+    }
+
+    /// The number of samples which contributed to the ungrouped `Metric`s of `metric_type`, or 0
+    /// when there are none. Ungrouped metrics of the same type are reported the same samples,
+    /// whilst a grouped metric's own `Metric::sample_count()` only counts its group.
+    #[must_use]
+    pub fn sample_count(&self, metric_type: MetricType) -> usize {
+        self.metrics
+            .values()
+            .find(|metric| metric.metric() == metric_type && metric.group().is_none())
+            .map_or(0, Metric::sample_count)
+    }
 
-    pub fn push(&mut self, m: Metric) {
-        self.metrics.insert(m.metric(), m);
+    /// Returns the `Metric` with `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Metric> {
+        self.metrics.get(name)
     }
 
-    pub fn calculate(&mut self, request_data: &[RequestData]) {
+    /// Add a `Metric`. When the name is already in use, the `Metric` is renamed with a `#n`
+    /// suffix, so that several metrics of the same type can be reported.
+    pub fn push(&mut self, mut m: Metric) {
+        let name = m.name().to_owned();
+        let mut n = 1;
+        while self.metrics.contains_key(m.name()) {
+            n += 1;
+            m = m.with_name(&format!("{name}#{n}"));
+        }
+        self.metrics.insert(m.name().to_owned(), m);
+    }
+
+    /// Calculate metrics from the `RequestData` of a simulation.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there are no metrics to calculate, or when they have already been
+    /// calculated. Metrics are left unchanged on error.
+    pub fn calculate(
+        &mut self,
+        request_data: &[RequestData],
+    ) -> core::result::Result<(), AggregatorError> {
+        if self.metrics.is_empty() {
+            return Err(AggregatorError::NoMetrics);
+        }
+        if self.calculated {
+            return Err(AggregatorError::AlreadyCalculated);
+        }
+
         for request in request_data {
             for metric in &mut self.metrics.values_mut() {
                 metric.report(request);
             }
         }
+        self.calculated = true;
+
+        Ok(())
+    }
+
+    /// Report requests as they complete, e.g. from `Simulation::take_completed()` after each tick,
+    /// so metrics can be queried with `get` whilst a simulation is running. Requests which are
+    /// still waiting when the simulation ends are never completed, so unlike `calculate` they are
+    /// not counted.
+    pub fn report(&mut self, request_data: &[RequestData]) {
+        for request in request_data {
+            for metric in self.metrics.values_mut() {
+                metric.report(request);
+            }
+        }
+    }
+
+    /// Combines an already computed `value` into every `Metric` of `metric_type`, e.g. to combine
+    /// the results of simulations which were sharded across machines. See `Metric::merge_value`.
+    ///
+    /// # Errors
+    ///
+    /// Will error if `value` is a different kind of `Value` to the metrics of `metric_type`.
+    /// Metrics are left unchanged on error.
+    pub fn merge_value(
+        &mut self,
+        metric_type: MetricType,
+        value: &Value,
+    ) -> core::result::Result<(), AggregatorError> {
+        let mut merged = self.metrics.clone();
+        for metric in merged.values_mut() {
+            if metric.metric() == metric_type {
+                metric
+                    .merge_value(value)
+                    .map_err(|_| AggregatorError::MismatchedValue(metric_type))?;
+            }
+        }
+        self.metrics = merged;
+
+        Ok(())
+    }
+
+    /// Clears all calculated values, allowing `calculate` to be called again.
+    pub fn reset(&mut self) {
+        for metric in self.metrics.values_mut() {
+            metric.reset();
+        }
+        self.calculated = false;
+    }
+
+    /// Resets, then calculates metrics from the `RequestData` of a simulation.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there are no metrics to calculate.
+    pub fn recalculate(
+        &mut self,
+        request_data: &[RequestData],
+    ) -> core::result::Result<(), AggregatorError> {
+        self.reset();
+        self.calculate(request_data)
+    }
+
+    /// Calculate metrics from both the `RequestData` and `ServerStats` of a simulation. Request
+    /// based metrics ignore `servers`, and server based metrics ignore `requests`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there are no metrics to calculate, or when they have already been
+    /// calculated. Metrics are left unchanged on error.
+    pub fn calculate_with(
+        &mut self,
+        requests: &[RequestData],
+        servers: &[ServerStats],
+    ) -> core::result::Result<(), AggregatorError> {
+        self.calculate(requests)?;
+
+        for server in servers {
+            for metric in self.metrics.values_mut() {
+                metric.report_server(server);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Calculate metrics from everything a completed `Simulation` records: its requests, servers,
+    /// and queue depth. This is the same as calling `calculate_with` and `calculate_queue_depth`.
+    /// Without a recorded queue depth series, the simulation's peak is reported as one sample.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there are no metrics to calculate, or when they have already been
+    /// calculated. Metrics are left unchanged on error.
+    pub fn calculate_simulation(
+        &mut self,
+        sim: &Simulation,
+    ) -> core::result::Result<(), AggregatorError> {
+        self.calculate_with(&sim.request_data(), &sim.server_stats())?;
+        self.calculate_queue_depth(&queue_depth(sim));
+
+        Ok(())
+    }
+
+    /// Evaluates the metrics of a completed `Simulation` into a `Run`, the same as
+    /// `calculate_simulation` would, without changing or cloning this `Aggregator`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there are no metrics to evaluate.
+    pub fn evaluate(&self, sim: &Simulation) -> core::result::Result<Run, AggregatorError> {
+        if self.metrics.is_empty() {
+            return Err(AggregatorError::NoMetrics);
+        }
+
+        let (requests, servers) = (sim.request_data(), sim.server_stats());
+        let queue_depth = queue_depth(sim);
+        let values = self
+            .metrics
+            .values()
+            .map(|metric| metric.evaluate(&requests, &servers, &queue_depth))
+            .collect();
+
+        Ok(Run {
+            simulation: usize::default(),
+            seed: None,
+            values,
+        })
+    }
+
+    /// Replaces the values of every metric, along with the simulation id and seed, with those of
+    /// `run`. The metrics are then calculated, as if by `calculate_simulation`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `run` was evaluated by an `Aggregator` with a different number of metrics.
+    /// Metrics are left unchanged on error.
+    pub fn load(&mut self, run: &Run) -> core::result::Result<(), AggregatorError> {
+        if run.values.len() != self.metrics.len() {
+            return Err(AggregatorError::MismatchedRun);
+        }
+
+        for (metric, (value, samples)) in self.metrics.values_mut().zip(&run.values) {
+            metric.load(value.clone(), *samples);
+        }
+        self.simulation = run.simulation;
+        self.seed = run.seed;
+        self.calculated = true;
+
+        Ok(())
+    }
+
+    /// Calculate metrics from the `(tick, waiting_count)` samples of a simulation.
+    pub fn calculate_queue_depth(&mut self, queue_depth: &[(Duration, usize)]) {
+        for (_, depth) in queue_depth {
+            for metric in self.metrics.values_mut() {
+                metric.report_queue_depth(*depth);
+            }
+        }
+    }
+}
+
+/// The queue depth series of `sim`, or its peak as a single sample when the series isn't recorded.
+fn queue_depth(sim: &Simulation) -> Cow<'_, [(Duration, usize)]> {
+    match sim.queue_depth_series() {
+        [] => Cow::Owned(vec![(sim.running().1, sim.peak_queue_depth())]),
+        series => Cow::Borrowed(series),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::answered;
+    use crate::Target;
+    use awt_simulation::attribute::Attribute;
+    use awt_simulation::request::{Data, Status};
+
+    fn answer_count() -> Aggregator {
+        let metric = Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap();
+        Aggregator::with_metrics(&[metric])
+    }
+
+    #[test]
+    fn errors_without_metrics() {
+        let mut aggregator = Aggregator::with_metrics(&[]);
+
+        assert_eq!(Err(AggregatorError::NoMetrics), aggregator.calculate(&[]));
+    }
+
+    #[test]
+    fn errors_when_already_calculated() {
+        let mut aggregator = answer_count();
+
+        assert_eq!(Ok(()), aggregator.calculate(&[]));
+        assert_eq!(
+            Err(AggregatorError::AlreadyCalculated),
+            aggregator.calculate(&[])
+        );
+    }
+
+    #[test]
+    fn merges_sharded_values_weighted_by_samples() {
+        let metric = Metric::with_target(MetricType::AbandonRate, Target::percent(0.5)).unwrap();
+        let request = |status| Data {
+            status,
+            wait_time: None,
+            handle_time: None,
+            clean_up_time: None,
+            served_by: None,
+            ..answered()
+        };
+        let shard = |abandoned: usize, answered: usize| {
+            let mut shard = Aggregator::with_metrics(&[metric.clone()]);
+            let data: Vec<Data> = core::iter::repeat(Status::Abandoned)
+                .take(abandoned)
+                .chain(core::iter::repeat(Status::Answered).take(answered))
+                .map(request)
+                .collect();
+            shard.calculate(&data).unwrap();
+            shard.get("AbandonRate").unwrap().value().clone()
+        };
+
+        // 3 of 4 and 1 of 6 abandon, so 4 of 10 overall rather than the mean of the two rates
+        let mut coordinator = Aggregator::with_metrics(&[metric.clone()]);
+        coordinator
+            .merge_value(MetricType::AbandonRate, &shard(3, 1))
+            .unwrap();
+        coordinator
+            .merge_value(MetricType::AbandonRate, &shard(1, 5))
+            .unwrap();
+
+        assert_eq!(
+            Some(0.4),
+            coordinator.get("AbandonRate").unwrap().value().as_f64()
+        );
+        assert_eq!(
+            Err(AggregatorError::MismatchedValue(MetricType::AbandonRate)),
+            coordinator.merge_value(MetricType::AbandonRate, &Value::default_count())
+        );
+    }
+
+    #[test]
+    fn recalculating_does_not_accumulate() {
+        let mut aggregator = answer_count();
+        let answered = answered();
+
+        let data = [answered];
+
+        aggregator.calculate(&data).unwrap();
+        aggregator.recalculate(&data).unwrap();
+
+        assert_eq!("1", format!("{}", aggregator.metrics().next().unwrap()));
+        assert!(aggregator.all_on_target());
+    }
+
+    #[test]
+    fn cleaning_matches_a_fresh_aggregator() {
+        let metrics = [
+            Metric::with_target(MetricType::AnswerCount, Target::count(2)).unwrap(),
+            Metric::with_target(
+                MetricType::AverageSpeedAnswer,
+                Target::mean_duration(Duration::from_secs(20)),
+            )
+            .unwrap(),
+        ];
+        let answered = |wait: u64| Data {
+            wait_time: Some(Duration::from_secs(wait)),
+            ..answered()
+        };
+
+        let mut reused = Aggregator::with_metrics(&metrics);
+        reused.set_simulation(1);
+        reused.set_seed(42);
+        reused.calculate(&[answered(60)]).unwrap();
+        reused.clean();
+        reused.calculate(&[answered(10), answered(20)]).unwrap();
+
+        let mut fresh = Aggregator::with_metrics(&metrics);
+        fresh.calculate(&[answered(10), answered(20)]).unwrap();
+
+        assert_eq!(None, reused.seed());
+        assert_eq!(fresh.to_string(), reused.to_string());
+        assert!(reused.all_on_target());
+    }
+
+    #[test]
+    fn abandon_rate_excludes_pending_requests() {
+        let metric = Metric::with_target(MetricType::AbandonRate, Target::percent(0.5)).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[metric]);
+        let request = |status| Data {
+            status,
+            wait_time: None,
+            handle_time: None,
+            clean_up_time: None,
+            served_by: None,
+            ..answered()
+        };
+
+        aggregator
+            .calculate(&[
+                request(Status::Abandoned),
+                request(Status::Answered),
+                request(Status::Pending),
+                request(Status::Pending),
+            ])
+            .unwrap();
+
+        assert_eq!("0.5", format!("{}", aggregator.get("AbandonRate").unwrap()));
+    }
+
+    #[test]
+    fn work_time_excludes_clean_up_time() {
+        let target = Target::mean_duration(Duration::from_secs(300));
+        let metrics = [
+            Metric::with_target(MetricType::AverageWorkTime, target.clone()).unwrap(),
+            Metric::with_target(MetricType::AverageCleanUpTime, target).unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+
+        aggregator
+            .calculate(&[Data {
+                handle_time: Some(Duration::from_secs(300)),
+                clean_up_time: Some(Duration::from_secs(60)),
+                ..answered()
+            }])
+            .unwrap();
+
+        let value = |name| format!("{}", aggregator.get(name).unwrap());
+        assert_eq!("300s", value("AverageWorkTime"));
+        assert_eq!("60s", value("AverageCleanUpTime"));
+    }
+
+    #[test]
+    fn scores_on_and_off_target_metrics() {
+        let work_time = Target::mean_duration(Duration::from_secs(300));
+        let clean_up_time = Target::mean_duration(Duration::from_secs(30));
+        let metrics = [
+            Metric::with_target(MetricType::AverageWorkTime, work_time)
+                .unwrap()
+                .with_score(1.0, 10.0, 10.0),
+            Metric::with_target(MetricType::AverageCleanUpTime, clean_up_time)
+                .unwrap()
+                .with_score(0.5, 10.0, 4.0),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+
+        aggregator
+            .calculate(&[Data {
+                handle_time: Some(Duration::from_secs(300)),
+                clean_up_time: Some(Duration::from_secs(60)),
+                ..answered()
+            }])
+            .unwrap();
+
+        // Work time is on target (+10), clean up time is double its target (-0.5 * 4 * 1.0)
+        assert!((aggregator.score() - 8.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn displays_a_table_of_metrics() {
+        let metrics = [
+            Metric::with_target(
+                MetricType::ServiceLevel(Duration::from_millis(20_500)),
+                Target::percent(0.8),
+            )
+            .unwrap(),
+            Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap(),
+            Metric::with_target(
+                MetricType::AverageWorkTime,
+                Target::mean_duration(Duration::from_secs(300)),
+            )
+            .unwrap()
+            .with_name("A rather long name for a metric"),
+            Metric::with_target(
+                MetricType::AverageTimeToAbandon,
+                Target::mean_duration(Duration::from_secs(30)),
+            )
+            .unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+        aggregator.set_simulation(3);
+
+        aggregator
+            .calculate(&[Data {
+                wait_time: Some(Duration::from_secs(10)),
+                handle_time: Some(Duration::from_secs(360)),
+                ..answered()
+            }])
+            .unwrap();
+
+        assert_eq!(
+            "\
+Statistics for simulation_id: 3
+Metric                         Target       Value        On Target Samples
+A rather long name for a metric 300s         360s         false     1
+AnswerCount                    1            1            true      1
+AverageTimeToAbandon           30s          None         false     0
+ServiceLevel(20.5s)            0.8          1            true      1
+",
+            aggregator.to_string()
+        );
+    }
+
+    #[test]
+    fn displays_metrics_in_the_same_order() {
+        let metrics = [
+            Metric::with_target(MetricType::PeakQueueLength, Target::count(5)).unwrap(),
+            Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap(),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.1)).unwrap(),
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.8)).unwrap(),
+        ];
+        let mut reversed = metrics.clone();
+        reversed.reverse();
+
+        let first = Aggregator::with_metrics(&metrics);
+        let second = Aggregator::with_metrics(&reversed);
+        let names: Vec<&str> = first.metrics().map(Metric::name).collect();
+
+        assert_eq!(
+            vec![
+                "AbandonRate",
+                "AnswerCount",
+                "PeakQueueLength",
+                "UtilisationTime"
+            ],
+            names
+        );
+        assert_eq!(first.to_string(), second.to_string());
+        assert_eq!(format!("{first:?}"), format!("{second:?}"));
+    }
+
+    #[test]
+    fn registers_metrics_of_the_same_type() {
+        let twenty = Duration::from_secs(20);
+        let sixty = Duration::from_secs(60);
+        let metrics = [
+            Metric::with_target(MetricType::ServiceLevel(twenty), Target::percent(0.8)).unwrap(),
+            Metric::with_target(MetricType::ServiceLevel(sixty), Target::percent(0.9)).unwrap(),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.1)).unwrap(),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.2))
+                .unwrap()
+                .with_name("LenientAbandonRate"),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.3)).unwrap(),
+        ];
+        let aggregator = Aggregator::with_metrics(&metrics);
+
+        assert_eq!(5, aggregator.metrics().count());
+        assert_eq!(
+            Some(MetricType::ServiceLevel(twenty)),
+            aggregator.get("ServiceLevel(20s)").map(Metric::metric)
+        );
+        assert_eq!(
+            Some(MetricType::ServiceLevel(sixty)),
+            aggregator.get("ServiceLevel(60s)").map(Metric::metric)
+        );
+        assert!(aggregator.get("AbandonRate").is_some());
+        assert!(aggregator.get("LenientAbandonRate").is_some());
+        assert!(aggregator.get("AbandonRate#2").is_some());
+    }
+
+    fn server(busy: u64) -> ServerStats {
+        ServerStats {
+            id: 0,
+            busy: Duration::from_secs(busy),
+            available: Duration::from_secs(120),
+        }
+    }
+
+    #[test]
+    fn averages_idle_time() {
+        let target = Target::mean_duration(Duration::from_secs(60));
+        let metric = Metric::with_target(MetricType::AverageIdleTime, target).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[metric]);
+
+        aggregator
+            .calculate_with(&[], &[server(90), server(30)])
+            .unwrap();
+
+        assert_eq!(
+            "60s",
+            format!("{}", aggregator.get("AverageIdleTime").unwrap())
+        );
+        assert!(aggregator.all_on_target());
+    }
+
+    #[test]
+    fn calculates_utilisation_from_servers() {
+        let metric =
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.8)).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[metric]);
+
+        aggregator
+            .calculate_with(&[], &[server(120), server(60)])
+            .unwrap();
+
+        assert_eq!(
+            "0.75",
+            format!("{}", aggregator.get("UtilisationTime").unwrap())
+        );
+        assert!(!aggregator.all_on_target());
+    }
+
+    #[test]
+    fn records_peak_queue_length() {
+        let metric = Metric::with_target(MetricType::PeakQueueLength, Target::count(1)).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[metric]);
+
+        let second = Duration::from_secs(1);
+        aggregator.calculate_queue_depth(&[(Duration::ZERO, 1), (second, 2), (second * 2, 0)]);
+
+        let peak = aggregator.metrics().next().unwrap();
+        assert_eq!("2", format!("{peak}"));
+        assert!(!aggregator.all_on_target());
+    }
+
+    #[test]
+    fn reports_abandon_rate_whilst_running() {
+        use awt_simulation::{client::Client, server::Server};
+        use rand::rngs::mock::StepRng;
+
+        let metric = Metric::with_target(MetricType::AbandonRate, Target::percent(0.1)).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[metric]);
+
+        let rng = Box::new(StepRng::new(1, u64::MAX / 3600));
+        let mut sim = Simulation::new(Duration::from_secs(3600), Duration::from_secs(1), rng);
+        for _ in 0..20 {
+            sim.add_client(Client::default()).unwrap();
+        }
+        sim.add_server(Server::default()).unwrap();
+        sim.enable().unwrap();
+
+        let mut rates = Vec::new();
+        loop {
+            let running = sim.tick();
+            aggregator.report(&sim.take_completed());
+            let rate = aggregator.get("AbandonRate").unwrap().value().as_f64();
+            if rates.last() != Some(&rate) {
+                rates.push(rate);
+            }
+            if !running {
+                break;
+            }
+        }
+
+        // Nothing has completed on the first tick, then the rate changes as requests abandon
+        assert_eq!(None, rates[0]);
+        assert!(rates.len() > 2, "rates {rates:?}");
+
+        // Matches calculating from every completed request at the end
+        let completed: Vec<Data> = sim
+            .request_data()
+            .into_iter()
+            .filter(|data| matches!(data.status, Status::Answered | Status::Abandoned))
+            .collect();
+        aggregator.recalculate(&completed).unwrap();
+        let rate = aggregator.get("AbandonRate").unwrap().value().as_f64();
+        assert_eq!(rate, *rates.last().unwrap());
+    }
+
+    #[test]
+    fn counts_samples_of_answered_and_abandoned_requests() {
+        let metrics = [
+            Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap(),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.1)).unwrap(),
+            Metric::with_target(
+                MetricType::AverageSpeedAnswer,
+                Target::mean_duration(Duration::from_secs(20)),
+            )
+            .unwrap(),
+            Metric::with_target(
+                MetricType::AverageTimeToAbandon,
+                Target::mean_duration(Duration::from_secs(30)),
+            )
+            .unwrap(),
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.8)).unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+
+        let request = |status, wait_time| Data {
+            status,
+            wait_time,
+            handle_time: None,
+            clean_up_time: None,
+            served_by: None,
+            ..answered()
+        };
+        let second = Some(Duration::from_secs(1));
+        aggregator
+            .calculate(&[
+                request(Status::Answered, second),
+                request(Status::Answered, second),
+                request(Status::Abandoned, second),
+                request(Status::Enqueued, None),
+                request(Status::Pending, None),
+            ])
+            .unwrap();
+
+        assert_eq!(2, aggregator.sample_count(MetricType::AnswerCount));
+        assert_eq!(2, aggregator.sample_count(MetricType::AverageSpeedAnswer));
+        assert_eq!(1, aggregator.sample_count(MetricType::AverageTimeToAbandon));
+        // Every enqueued request counts towards the abandon rate
+        assert_eq!(4, aggregator.sample_count(MetricType::AbandonRate));
+        // Server based, so no requests contribute
+        assert_eq!(0, aggregator.sample_count(MetricType::UtilisationTime));
+        assert_eq!(0, aggregator.sample_count(MetricType::PeakQueueLength));
+    }
+
+    #[test]
+    fn sample_count_skips_grouped_metrics() {
+        let vip = Attribute::new(0, None);
+        let count = || Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap();
+        // Sorts before `AnswerCount`
+        let grouped = count().with_name("Accepted").with_group(vec![vip], "VIP");
+        let mut aggregator = Aggregator::with_metrics(&[grouped, count()]);
+
+        aggregator.calculate(&[answered(), answered()]).unwrap();
+
+        assert_eq!(0, aggregator.get("Accepted[VIP]").unwrap().sample_count());
+        assert_eq!(2, aggregator.sample_count(MetricType::AnswerCount));
+    }
+
+    #[test]
+    fn calculates_from_a_simulation_config() {
+        use awt_simulation::{client::Client, server::Server, Config};
+        use rand::rngs::mock::StepRng;
+
+        let metrics = [
+            Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap(),
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.5)).unwrap(),
+            Metric::with_target(MetricType::PeakQueueLength, Target::count(10)).unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+
+        let rng = Box::new(StepRng::new(1, u64::MAX / 3600));
+        let mut config = Config::new(Duration::from_secs(3600), Duration::from_secs(1), rng);
+        config.set_clients(vec![Client::default(); 5]);
+        config.add_server(Server::default());
+
+        let mut sim = Simulation::from(config);
+        sim.enable().unwrap();
+        while sim.tick() {}
+
+        assert_eq!(Ok(()), aggregator.calculate_simulation(&sim));
+        // Each kind of data the simulation records was reported
+        assert!(aggregator.sample_count(MetricType::AnswerCount) > 0);
+        assert_eq!(1, aggregator.sample_count(MetricType::UtilisationTime));
+        assert!(aggregator.sample_count(MetricType::PeakQueueLength) > 0);
+        assert_eq!(
+            Err(AggregatorError::AlreadyCalculated),
+            aggregator.calculate_simulation(&sim)
+        );
+    }
+
+    #[test]
+    fn evaluates_runs_like_calculating() {
+        use awt_simulation::{client::Client, server::Server, Config};
+        use rand::rngs::mock::StepRng;
+
+        let metrics = [
+            Metric::with_target(MetricType::AnswerCount, Target::count(1)).unwrap(),
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.5)).unwrap(),
+            Metric::with_target(MetricType::PeakQueueLength, Target::count(10)).unwrap(),
+            Metric::wait_time_histogram(vec![Duration::from_secs(10)]),
+        ];
+        let aggregator = Aggregator::with_metrics(&metrics);
+
+        let simulation = |clients: usize| {
+            let rng = Box::new(StepRng::new(1, u64::MAX / 3600));
+            let mut config = Config::new(Duration::from_secs(3600), Duration::from_secs(1), rng);
+            config.set_clients(vec![Client::default(); clients]);
+            config.add_server(Server::default());
+
+            let mut sim = Simulation::from(config);
+            sim.enable().unwrap();
+            while sim.tick() {}
+            sim
+        };
+        let sims = [simulation(5), simulation(20)];
+
+        // Runs only borrow the aggregator, whilst calculating needs a clone for each simulation
+        let runs: Vec<Run> = sims
+            .iter()
+            .enumerate()
+            .map(|(index, sim)| {
+                let mut run = aggregator.evaluate(sim).unwrap();
+                run.set_simulation(index);
+                run.set_seed(42);
+                run
+            })
+            .collect();
+
+        let mut loaded = aggregator.clone();
+        for (index, (sim, run)) in sims.iter().zip(&runs).enumerate() {
+            let mut calculated = aggregator.clone();
+            calculated.set_simulation(index);
+            calculated.set_seed(42);
+            calculated.calculate_simulation(sim).unwrap();
+
+            assert_eq!(Ok(()), loaded.load(run));
+            assert_eq!(calculated.to_string(), loaded.to_string());
+        }
+        assert_eq!(
+            Err(AggregatorError::AlreadyCalculated),
+            loaded.calculate_simulation(&sims[0])
+        );
+    }
+
+    #[test]
+    fn loads_only_runs_of_the_same_metrics() {
+        use rand::rngs::mock::StepRng;
+
+        let second = Duration::from_secs(1);
+        let sim = Simulation::new(second, second, Box::new(StepRng::new(1, 1)));
+        let run = answer_count().evaluate(&sim).unwrap();
+        let mut aggregator = Aggregator::with_metrics(&[]);
+
+        assert_eq!(
+            Err(AggregatorError::NoMetrics),
+            aggregator.evaluate(&sim).map(|_| ())
+        );
+        assert_eq!(Err(AggregatorError::MismatchedRun), aggregator.load(&run));
+    }
+
+    #[test]
+    fn calculates_an_empty_simulation() {
+        use awt_simulation::Config;
+        use rand::rngs::mock::StepRng;
+
+        let second = Duration::from_secs(1);
+        let mean = || Target::mean_duration(second);
+        let metrics = [
+            Metric::with_target(MetricType::ServiceLevel(second), Target::percent(0.8)).unwrap(),
+            Metric::with_target(MetricType::AverageWorkTime, mean()).unwrap(),
+            Metric::with_target(MetricType::AverageCleanUpTime, mean()).unwrap(),
+            Metric::with_target(MetricType::AverageSpeedAnswer, mean()).unwrap(),
+            Metric::with_target(MetricType::AverageTimeToAbandon, mean()).unwrap(),
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.1)).unwrap(),
+            Metric::with_target(MetricType::AverageTimeInQueue, mean()).unwrap(),
+            Metric::with_target(MetricType::UtilisationTime, Target::percent(0.8)).unwrap(),
+            Metric::with_target(MetricType::AnswerCount, Target::count(0)).unwrap(),
+            Metric::with_target(MetricType::PeakQueueLength, Target::count(0)).unwrap(),
+            Metric::with_target(MetricType::AverageIdleTime, mean()).unwrap(),
+            Metric::wait_time_histogram(vec![second]),
+            Metric::with_target(MetricType::CallbackRate, Target::percent(0.5)).unwrap(),
+            Metric::with_target(MetricType::AverageCallbackDelay, mean()).unwrap(),
+            Metric::with_target(MetricType::TransferRate, Target::percent(0.1)).unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+
+        let rng = Box::new(StepRng::new(1, 1));
+        let mut sim = Simulation::from(Config::new(Duration::from_secs(3600), second, rng));
+        sim.enable().unwrap();
+        while sim.tick() {}
+
+        aggregator.calculate_simulation(&sim).unwrap();
+
+        let on_target = ["AnswerCount", "PeakQueueLength", "WaitTimeHistogram"];
+        for metric in aggregator.metrics() {
+            assert_eq!(on_target.contains(&metric.name()), metric.on_target());
+            assert!(metric.score().is_finite());
+        }
+        assert_eq!(
+            "None",
+            aggregator.get("AverageWorkTime").unwrap().to_string()
+        );
+        assert_eq!("None", aggregator.get("AbandonRate").unwrap().to_string());
+        assert_eq!(
+            "None",
+            aggregator.get("UtilisationTime").unwrap().to_string()
+        );
+        assert_eq!("0", aggregator.get("AnswerCount").unwrap().to_string());
+        assert_eq!(
+            "0s-1s: 0, 1s+: 0",
+            aggregator.get("WaitTimeHistogram").unwrap().to_string()
+        );
+        assert!(!aggregator.all_on_target());
+
+        let mut summary = crate::Summary::default();
+        summary.push(&aggregator);
+        assert!(summary.to_string().contains("None"));
     }
 }
@@ -15,15 +15,19 @@ extern crate alloc;
 use core::time::Duration;
 use core::{fmt, fmt::Display, fmt::Formatter};
 
-use awt_simulation::request::{Data as RequestData, Status};
+use awt_simulation::attribute::Attribute;
+use awt_simulation::request::{AbandonReason, Data as RequestData, Status};
+use awt_simulation::server::Stats as ServerStats;
 
 mod aggregator;
+mod summary;
 mod target;
 mod value;
 
-pub use aggregator::Aggregator;
+pub use aggregator::{Aggregator, AggregatorError, Run};
+pub use summary::Summary;
 pub use target::{Target, TargetCondition};
-use value::Value;
+pub use value::{Count, Histogram, MeanDuration, Percent, Value};
 
 /// Enumerates a metric to trace on a `Request`.
 #[allow(clippy::module_name_repetitions)]
@@ -32,28 +36,70 @@ use value::Value;
 pub enum MetricType {
     /// Percent of `Client`s answered in `tick`.
     ServiceLevel(Duration),
-    /// Mean of work time of answered `Request`.
+    /// Mean of work time of answered `Request`, excluding clean up.
     AverageWorkTime,
+    /// Mean of clean up (ACW) time of answered `Request`.
+    AverageCleanUpTime,
     /// Mean for `tick` of answered `Request`.
     AverageSpeedAnswer,
-    /// Mean of `tick` of abandoned `Request` (at abandon).
+    /// Mean of `tick` of abandoned `Request` (at abandon). `Request`s which abandoned before being
+    /// queued are excluded.
     AverageTimeToAbandon,
-    /// Percent of `Request` abandoned vs. total `Request` count.
+    /// Percent of `Request` abandoned vs. total `Request` count. `Request`s which were never
+    /// enqueued (still `Pending` at the end of the simulation) are excluded, whilst those which
+    /// abandoned before being queued are included.
     AbandonRate,
-    /// Mean of `tick` of `Request` in queue for both answered and abandoned.
+    /// Mean of `tick` of `Request` in queue for both answered and abandoned. `Request`s which
+    /// abandoned before being queued are excluded.
     AverageTimeInQueue,
     /// Percent of `Server` time spent answering `Request`s.
     UtilisationTime,
     /// Count of Answered
     AnswerCount,
+    /// Largest number of `Request`s waiting at once.
+    PeakQueueLength,
+    /// Mean of the time each `Server` spent idle.
+    AverageIdleTime,
+    /// Count of answered `Request`s in each wait time bucket. Created with
+    /// `Metric::wait_time_histogram`, as the buckets are part of the value.
+    WaitTimeHistogram,
+    /// Percent of `Request`s which reached their abandon time that opted into a callback, rather
+    /// than abandoning. `Request`s which abandoned before being queued are excluded.
+    CallbackRate,
+    /// Mean of the time answered `Request`s waited from opting into a callback until answered.
+    AverageCallbackDelay,
+    /// Percent of answered `Request`s which were transferred, spawning a follow-up. Follow-ups are
+    /// excluded, so this is the complement of first contact resolution.
+    TransferRate,
+}
+
+/// The name of the variant, with the window of a `ServiceLevel` in seconds, e.g.
+/// `ServiceLevel(20s)`.
+impl Display for MetricType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ServiceLevel(window) => write!(f, "ServiceLevel({}s)", window.as_secs_f64()),
+            _ => write!(f, "{self:?}"),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Metric {
+    name: String,
     metric_type: MetricType,
     value: Value,
     target: Target,
     target_condition: TargetCondition,
+    weight: f64,
+    reward: f64,
+    penalty: f64,
+    /// Whether exceeding the target scores more than the reward, see
+    /// `with_reward_overachievement()`.
+    reward_overachievement: bool,
+    samples: usize,
+    /// Attributes a `Request` must require to be reported, see `with_group()`.
+    group: Option<Vec<Attribute>>,
 }
 
 impl Display for Metric {
@@ -74,72 +120,229 @@ impl Metric {
     /// `Target::MeanDuration`:
     ///
     /// - `MetricType::AverageWorkTime`
+    /// - `MetricType::AverageCleanUpTime`
     /// - `MetricType::AverageSpeedAnswer`
     /// - `MetricType::AverageTimeInQueue`
     /// - `MetricType::AverageTimeToAbandon`
+    /// - `MetricType::AverageIdleTime`
+    /// - `MetricType::AverageCallbackDelay`
     ///
     /// `Target::Percent`:
     ///
     /// - `MetricType::UtilisationTime`
     /// - `MetricType::ServiceLevel(_)`
     /// - `MetricType::AbandonRate`
+    /// - `MetricType::CallbackRate`
+    /// - `MetricType::TransferRate`
     ///
     /// `Target::Count`:
     ///
     /// - `MetricType::AnswerCount`
+    /// - `MetricType::PeakQueueLength`
+    ///
+    /// `Target::Percent` targets must be in the range of `0.0..=1.0`.
+    ///
+    /// `MetricType::AnswerCount` is on target with at least as many answers as the target. Use
+    /// `with_target_condition()` to compare differently.
     ///
     /// # Errors
     ///
-    /// Will error if not using the correct target mapping
+    /// Will error if not using the correct target mapping, or if a percent target is out of range
     #[allow(clippy::match_same_arms)]
     pub fn with_target(metric_type: MetricType, target: Target) -> Result<Self, MetricError> {
-        match (metric_type, target.clone()) {
+        if let Target::Percent(percent) = &target {
+            if !(0.0..=1.0).contains(&(percent.sum / percent.count)) {
+                return Err(MetricError {});
+            }
+        }
+
+        let (value, target_condition) = match (metric_type, target.clone()) {
             (
                 MetricType::AverageWorkTime
+                | MetricType::AverageCleanUpTime
                 | MetricType::AverageSpeedAnswer
                 | MetricType::AverageTimeInQueue
-                | MetricType::AverageTimeToAbandon,
+                | MetricType::AverageTimeToAbandon
+                | MetricType::AverageIdleTime
+                | MetricType::AverageCallbackDelay,
                 Target::MeanDuration(_),
-            ) => Ok(Self {
-                metric_type,
-                value: Value::default_mean_duration(),
-                target,
-                target_condition: TargetCondition::LesserOrEqual,
-            }),
-            (MetricType::UtilisationTime | MetricType::ServiceLevel(_), Target::Percent(_)) => {
-                Ok(Self {
-                    metric_type,
-                    value: Value::default_percent(),
-                    target,
-                    target_condition: TargetCondition::GreaterOrEqual,
-                })
-            }
-            (MetricType::AbandonRate, Target::Percent(_)) => Ok(Self {
-                metric_type,
-                value: Value::default_percent(),
-                target,
-                target_condition: TargetCondition::LesserOrEqual,
-            }),
-
-            (MetricType::AnswerCount, Target::Count(_)) => Ok(Self {
-                metric_type,
-                value: Value::default_count(),
-                target,
-                target_condition: TargetCondition::Equal,
-            }),
-            (_, Target::MeanDuration(_) | Target::Percent(_) | Target::Count(_)) => {
-                Err(MetricError {})
+            ) => (
+                Value::default_mean_duration(),
+                TargetCondition::LesserOrEqual,
+            ),
+            (
+                MetricType::UtilisationTime
+                | MetricType::ServiceLevel(_)
+                | MetricType::CallbackRate,
+                Target::Percent(_),
+            ) => (Value::default_percent(), TargetCondition::GreaterOrEqual),
+            (MetricType::AbandonRate | MetricType::TransferRate, Target::Percent(_)) => {
+                (Value::default_percent(), TargetCondition::LesserOrEqual)
+            }
+            (MetricType::AnswerCount, Target::Count(_)) => {
+                (Value::default_count(), TargetCondition::GreaterOrEqual)
+            }
+            (MetricType::PeakQueueLength, Target::Count(_)) => {
+                (Value::default_count(), TargetCondition::LesserOrEqual)
             }
+            (
+                _,
+                Target::MeanDuration(_)
+                | Target::Percent(_)
+                | Target::Count(_)
+                | Target::Histogram(_),
+            ) => return Err(MetricError {}),
+        };
+
+        Ok(Self {
+            name: metric_type.to_string(),
+            metric_type,
+            value,
+            target,
+            target_condition,
+            weight: 1.0,
+            reward: 0.0,
+            penalty: 0.0,
+            reward_overachievement: false,
+            samples: 0,
+            group: None,
+        })
+    }
+
+    /// Create a `MetricType::WaitTimeHistogram`, counting answered requests into wait time buckets
+    /// split at each of `bounds`. e.g. bounds of 10s and 20s count `0s-10s`, `10s-20s`, and `20s+`.
+    /// A histogram has no target, so it is always on target.
+    #[must_use]
+    pub fn wait_time_histogram(bounds: Vec<Duration>) -> Self {
+        let value = Value::Histogram(Histogram::new(bounds));
+        let metric_type = MetricType::WaitTimeHistogram;
+
+        Self {
+            name: metric_type.to_string(),
+            metric_type,
+            target: value.clone(),
+            value,
+            target_condition: TargetCondition::Equal,
+            weight: 1.0,
+            reward: 0.0,
+            penalty: 0.0,
+            reward_overachievement: false,
+            samples: 0,
+            group: None,
         }
     }
 
+    /// Sets the name this `Metric` is reported with. Default is the name of the `MetricType`.
+    #[must_use]
+    pub fn with_name(mut self, name: &str) -> Self {
+        name.clone_into(&mut self.name);
+        self
+    }
+
+    /// Only reports `Request`s whose required attributes satisfy every attribute of `group`, e.g.
+    /// a `ServiceLevel` of VIP requests. `label` is appended to the name, e.g.
+    /// `ServiceLevel(10s)[VIP]`. Metrics of `Server`s and the queue are unaffected.
+    #[must_use]
+    pub fn with_group(mut self, group: Vec<Attribute>, label: &str) -> Self {
+        self.name = format!("{}[{label}]", self.name);
+        self.group = Some(group);
+        self
+    }
+
+    /// Sets how the value is compared to the target in `on_target()`. Default depends on the
+    /// `MetricType`.
+    #[must_use]
+    pub fn with_target_condition(mut self, target_condition: TargetCondition) -> Self {
+        self.target_condition = target_condition;
+        self
+    }
+
+    /// Sets the `weight`, `reward`, and `penalty` used by `score()`. Default is a weight of 1.0
+    /// with no reward or penalty.
+    #[must_use]
+    pub fn with_score(mut self, weight: f64, reward: f64, penalty: f64) -> Self {
+        self.weight = weight;
+        self.reward = reward;
+        self.penalty = penalty;
+        self
+    }
+
+    /// Sets whether a value beyond the target scores more than the reward, scaled by how far it is
+    /// beyond the target relative to the target. e.g. a service level of 0.96 against a 0.8 target
+    /// scores 1.2 times the reward. Default is false, where over achieving scores only the reward.
+    #[must_use]
+    pub fn with_reward_overachievement(mut self, reward_overachievement: bool) -> Self {
+        self.reward_overachievement = reward_overachievement;
+        self
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     #[must_use]
     pub fn metric(&self) -> MetricType {
         self.metric_type
     }
 
+    /// The attributes a `Request` must require to be reported, see `with_group()`.
+    #[must_use]
+    pub fn group(&self) -> Option<&[Attribute]> {
+        self.group.as_deref()
+    }
+
+    /// The value reported so far, e.g. to be combined with `merge_value` elsewhere.
+    #[must_use]
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The mean reported so far, when the value is a `MeanDuration`. `None` for any other kind of
+    /// value, or when nothing has been reported.
+    #[must_use]
+    pub fn value_duration(&self) -> Option<Duration> {
+        match &self.value {
+            Value::MeanDuration(m) => m.mean(),
+            _ => None,
+        }
+    }
+
+    /// The ratio reported so far, e.g. `0.8` for 80%, when the value is a `Percent`. `None` for
+    /// any other kind of value, or when nothing has been reported.
+    #[must_use]
+    pub fn value_percent(&self) -> Option<f64> {
+        match &self.value {
+            Value::Percent(p) => p.ratio(),
+            _ => None,
+        }
+    }
+
+    /// The count reported so far, when the value is a `Count`. `None` for any other kind of value.
+    #[must_use]
+    pub fn value_count(&self) -> Option<usize> {
+        match &self.value {
+            Value::Count(c) => Some(c.count),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn target(&self) -> &Target {
+        &self.target
+    }
+
+    /// Returns whether the value meets the target. A `Metric` without any reported values is not
+    /// on target.
     #[must_use]
     pub fn on_target(&self) -> bool {
+        if let Value::Histogram(_) = self.value {
+            return true;
+        }
+        if self.value.as_f64().is_none() {
+            return false;
+        }
+
         match self.target_condition {
             TargetCondition::Equal if self.value == self.target => true,
             TargetCondition::LesserOrEqual if self.value <= self.target => true,
@@ -147,49 +350,602 @@ impl Metric {
             _ => false,
         }
     }
+
+    /// The weighted score of this `Metric`. Meeting the target scores the reward, plus any credit
+    /// for over achieving with `with_reward_overachievement()`. Missing the target loses the
+    /// penalty, scaled by how far the value is from the target relative to the target. e.g. an
+    /// average of 30s against a 20s target loses half of the penalty. At most the whole penalty is
+    /// lost, which is also lost by a metric without any reported values.
+    #[must_use]
+    pub fn score(&self) -> f64 {
+        if self.on_target() {
+            let beyond = if self.reward_overachievement {
+                self.distance_from_target().unwrap_or(0.0)
+            } else {
+                0.0
+            };
+            return self.weight * self.reward * (1.0 + beyond);
+        }
+
+        let Some(miss) = self.distance_from_target() else {
+            return -self.weight * self.penalty;
+        };
+
+        -self.weight * self.penalty * miss.min(1.0)
+    }
+
+    /// How far the value is from the target, relative to the target unless the target is zero.
+    /// `None` without any reported values, or for a histogram.
+    fn distance_from_target(&self) -> Option<f64> {
+        let (value, target) = (self.value.as_f64()?, self.target.as_f64()?);
+
+        Some(if target == 0.0 {
+            (value - target).abs()
+        } else {
+            ((value - target) / target).abs()
+        })
+    }
 }
 
 // Reporting functions
 impl Metric {
     /// Report a value for this metric
-    ///
-    /// # Panics
-    ///
-    /// Will panic if attempt to report for other aggregate types
-    #[allow(clippy::match_same_arms)]
     pub fn report(&mut self, r: &RequestData) {
-        match (self.metric_type, r.status, &mut self.value) {
+        if Self::report_to(self.metric_type, self.group.as_deref(), &mut self.value, r) {
+            self.samples += 1;
+        }
+    }
+
+    /// Reports `r` to `value` as a metric of `metric_type` and `group`. Returns whether it was
+    /// reported, i.e. is a sample.
+    #[allow(clippy::match_same_arms)]
+    fn report_to(
+        metric_type: MetricType,
+        group: Option<&[Attribute]>,
+        value: &mut Value,
+        r: &RequestData,
+    ) -> bool {
+        if let Some(group) = group {
+            if !Attribute::satisfies_all(&r.required_attributes, group) {
+                return false;
+            }
+        }
+
+        // Requests which abandoned before being queued never waited
+        let queued = r.abandon_reason != Some(AbandonReason::PreQueue);
+
+        let reported = match (metric_type, r.status, value) {
             (MetricType::ServiceLevel(ticks), Status::Answered, Value::Percent(m)) => {
-                if let Some(tick) = r.wait_time {
-                    m.report(tick <= ticks);
-                }
+                r.wait_time.map(|tick| m.report(tick <= ticks))
             }
             (MetricType::AverageWorkTime, Status::Answered, Value::MeanDuration(m)) => {
-                if let Some(tick) = r.handle_time {
-                    m.report(tick);
-                }
+                r.handle_time.map(|tick| m.report(tick))
+            }
+            (MetricType::AverageCleanUpTime, Status::Answered, Value::MeanDuration(m)) => {
+                r.clean_up_time.map(|tick| m.report(tick))
             }
             (MetricType::AverageSpeedAnswer, Status::Answered, Value::MeanDuration(m)) => {
-                if let Some(tick) = r.wait_time {
-                    m.report(tick);
-                }
+                r.wait_time.map(|tick| m.report(tick))
             }
-            (MetricType::AverageTimeToAbandon, Status::Abandoned, Value::MeanDuration(m)) => {
-                if let Some(tick) = r.wait_time {
-                    m.report(tick);
-                }
+            (MetricType::AverageTimeToAbandon, Status::Abandoned, Value::MeanDuration(m))
+                if queued =>
+            {
+                r.wait_time.map(|tick| m.report(tick))
             }
+            (MetricType::AbandonRate, Status::Pending, _) => None,
             (MetricType::AbandonRate, _, Value::Percent(m)) => {
                 m.report(Status::Abandoned == r.status);
+                Some(())
+            }
+            (MetricType::AverageTimeInQueue, _, Value::MeanDuration(m)) if queued => {
+                r.wait_time.map(|tick| m.report(tick))
+            }
+            (MetricType::AnswerCount, Status::Answered, Value::Count(m)) => {
+                m.report();
+                Some(())
             }
-            (MetricType::AverageTimeInQueue, _, Value::MeanDuration(m)) => {
-                if let Some(tick) = r.wait_time {
-                    m.report(tick);
-                }
+            (MetricType::WaitTimeHistogram, Status::Answered, Value::Histogram(m)) => {
+                r.wait_time.map(|tick| m.report(tick))
             }
-            (MetricType::AnswerCount, Status::Answered, Value::Count(m)) => m.report(),
-            (MetricType::UtilisationTime, _, _) => todo!(),
-            _ => (),
+            (MetricType::CallbackRate, _, Value::Percent(m))
+                if queued && (r.callback_time.is_some() || Status::Abandoned == r.status) =>
+            {
+                m.report(r.callback_time.is_some());
+                Some(())
+            }
+            (MetricType::AverageCallbackDelay, Status::Answered, Value::MeanDuration(m)) => r
+                .callback_time
+                .zip(r.wait_time)
+                .and_then(|(callback, wait)| wait.checked_sub(callback))
+                .map(|delay| m.report(delay)),
+            (MetricType::TransferRate, Status::Answered, Value::Percent(m))
+                if r.transfer_of.is_none() =>
+            {
+                m.report(r.transferred_to.is_some());
+                Some(())
+            }
+            _ => None,
+        };
+
+        reported.is_some()
+    }
+
+    /// Combines an already computed `value` of this metric, e.g. from a simulation run elsewhere.
+    /// A `PeakQueueLength` keeps the larger of the two peaks, otherwise see `Value::merge`. The
+    /// sample count is unchanged, as a `Value` doesn't record its samples.
+    ///
+    /// # Errors
+    ///
+    /// Will error if `value` is a different kind of `Value` to this metric's.
+    pub fn merge_value(&mut self, value: &Value) -> Result<(), MetricError> {
+        match (self.metric_type, &mut self.value, value) {
+            (MetricType::PeakQueueLength, Value::Count(peak), Value::Count(other)) => {
+                peak.report_max(other.count);
+                Ok(())
+            }
+            (_, own, value) => own.merge(value),
+        }
+    }
+
+    /// Clears all reported values.
+    pub fn reset(&mut self) {
+        self.value.reset();
+        self.samples = 0;
+    }
+
+    /// The number of requests, servers, or queue depths which contributed to the value since it
+    /// was last reset. A metric which is never reported to has no samples, which may indicate it
+    /// does not apply to the simulation, e.g. an `AverageTimeToAbandon` when nothing abandons.
+    #[must_use]
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+
+    /// Report the `ServerStats` of a single `Server`. Metrics which are not server based ignore
+    /// these.
+    pub fn report_server(&mut self, s: &ServerStats) {
+        if Self::report_server_to(self.metric_type, &mut self.value, s) {
+            self.samples += 1;
         }
     }
+
+    fn report_server_to(metric_type: MetricType, value: &mut Value, s: &ServerStats) -> bool {
+        match (metric_type, value) {
+            (MetricType::AverageIdleTime, Value::MeanDuration(m)) => m.report(s.idle()),
+            (MetricType::UtilisationTime, Value::Percent(m)) => {
+                m.report_ratio(s.busy.as_secs_f64(), s.available.as_secs_f64());
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Report the number of `Request`s waiting at a point in time.
+    pub fn report_queue_depth(&mut self, depth: usize) {
+        if Self::report_queue_depth_to(self.metric_type, &mut self.value, depth) {
+            self.samples += 1;
+        }
+    }
+
+    fn report_queue_depth_to(metric_type: MetricType, value: &mut Value, depth: usize) -> bool {
+        let (MetricType::PeakQueueLength, Value::Count(m)) = (metric_type, value) else {
+            return false;
+        };
+        m.report_max(depth);
+        true
+    }
+
+    /// The value and sample count this metric would have after reporting everything a simulation
+    /// records, without changing this metric.
+    pub(crate) fn evaluate(
+        &self,
+        requests: &[RequestData],
+        servers: &[ServerStats],
+        queue_depth: &[(Duration, usize)],
+    ) -> (Value, usize) {
+        let mut value = self.value.clone();
+        value.reset();
+
+        let mut samples = 0;
+        for r in requests {
+            if Self::report_to(self.metric_type, self.group.as_deref(), &mut value, r) {
+                samples += 1;
+            }
+        }
+        for s in servers {
+            if Self::report_server_to(self.metric_type, &mut value, s) {
+                samples += 1;
+            }
+        }
+        for (_, depth) in queue_depth {
+            if Self::report_queue_depth_to(self.metric_type, &mut value, *depth) {
+                samples += 1;
+            }
+        }
+
+        (value, samples)
+    }
+
+    /// Replaces the value and sample count, e.g. with those from `evaluate`.
+    pub(crate) fn load(&mut self, value: Value, samples: usize) {
+        self.value = value;
+        self.samples = samples;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn answered() -> RequestData {
+        RequestData {
+            id: 0,
+            start: Duration::ZERO,
+            enqueued_at: None,
+            answered_or_abandoned_at: None,
+            status: Status::Answered,
+            wait_time: Some(Duration::ZERO),
+            handle_time: Some(Duration::ZERO),
+            clean_up_time: Some(Duration::ZERO),
+            served_by: Some(0),
+            abandon_reason: None,
+            callback_time: None,
+            transfer_of: None,
+            transferred_to: None,
+            required_attributes: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn answer_count(answers: usize, target_condition: Option<TargetCondition>) -> Metric {
+        let mut metric = Metric::with_target(MetricType::AnswerCount, Target::count(2)).unwrap();
+        if let Some(target_condition) = target_condition {
+            metric = metric.with_target_condition(target_condition);
+        }
+
+        for _ in 0..answers {
+            metric.report(&answered());
+        }
+        metric
+    }
+
+    #[test]
+    fn answer_count_is_on_target_with_at_least_target() {
+        assert!(!answer_count(1, None).on_target());
+        assert!(answer_count(2, None).on_target());
+        assert!(answer_count(3, None).on_target());
+    }
+
+    #[test]
+    fn answer_count_target_condition_can_be_changed() {
+        let equal = Some(TargetCondition::Equal);
+        assert!(answer_count(2, equal.clone()).on_target());
+        assert!(!answer_count(3, equal).on_target());
+
+        let lesser = Some(TargetCondition::LesserOrEqual);
+        assert!(answer_count(2, lesser.clone()).on_target());
+        assert!(!answer_count(3, lesser).on_target());
+    }
+
+    #[test]
+    fn on_target_scores_weighted_reward() {
+        let metric = answer_count(2, None).with_score(2.0, 10.0, 5.0);
+
+        assert!((metric.score() - 20.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn off_target_scores_scaled_penalty() {
+        // Half of the target is answered, so half of the penalty is lost
+        let metric = answer_count(1, None).with_score(2.0, 10.0, 5.0);
+
+        assert!((metric.score() + 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn over_achieving_is_only_rewarded_when_enabled() {
+        // Half again of the target is answered
+        let metric = answer_count(3, None).with_score(2.0, 10.0, 5.0);
+        assert!((metric.score() - 20.0).abs() < f64::EPSILON);
+
+        let metric = metric.with_reward_overachievement(true);
+        assert!((metric.score() - 30.0).abs() < f64::EPSILON);
+
+        // Exactly meeting the target has nothing extra to reward
+        let metric = answer_count(2, None)
+            .with_score(2.0, 10.0, 5.0)
+            .with_reward_overachievement(true);
+        assert!((metric.score() - 20.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn over_achieving_does_not_change_penalties() {
+        let metric = answer_count(1, None)
+            .with_score(2.0, 10.0, 5.0)
+            .with_reward_overachievement(true);
+
+        assert!((metric.score() + 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn unreported_scores_whole_penalty() {
+        let metric = Metric::with_target(MetricType::AbandonRate, Target::percent(0.1))
+            .unwrap()
+            .with_score(1.0, 10.0, 5.0);
+
+        assert!((metric.score() + 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn off_target_loses_at_most_whole_penalty() {
+        let mut metric = Metric::with_target(MetricType::AbandonRate, Target::percent(0.1))
+            .unwrap()
+            .with_score(1.0, 10.0, 5.0);
+        // Half abandoned is four times the target away from it
+        metric.report(&answered());
+        metric.report(&RequestData {
+            status: Status::Abandoned,
+            ..answered()
+        });
+
+        assert!((metric.score() + 5.0).abs() < f64::EPSILON);
+    }
+
+    /// A direct answer, two answered callbacks, an unanswered callback, and an abandon.
+    fn callback_outcomes() -> Vec<RequestData> {
+        let callback = |wait: Option<u64>| RequestData {
+            status: wait.map_or(Status::Enqueued, |_| Status::Answered),
+            wait_time: wait.map(Duration::from_secs),
+            callback_time: Some(Duration::from_secs(60)),
+            ..answered()
+        };
+
+        vec![
+            answered(),
+            callback(Some(90)),
+            callback(Some(150)),
+            callback(None),
+            RequestData {
+                status: Status::Abandoned,
+                wait_time: Some(Duration::from_secs(60)),
+                ..answered()
+            },
+        ]
+    }
+
+    #[test]
+    fn callback_rate_of_would_be_abandons() {
+        let mut metric =
+            Metric::with_target(MetricType::CallbackRate, Target::percent(0.7)).unwrap();
+        for request in callback_outcomes() {
+            metric.report(&request);
+        }
+
+        assert_eq!(4, metric.sample_count());
+        assert_eq!("0.75", metric.to_string());
+        assert!(metric.on_target());
+    }
+
+    #[test]
+    fn callback_delay_of_answered_callbacks() {
+        let mut metric = Metric::with_target(
+            MetricType::AverageCallbackDelay,
+            Target::mean_duration(Duration::from_secs(30)),
+        )
+        .unwrap();
+        for request in callback_outcomes() {
+            metric.report(&request);
+        }
+
+        assert_eq!(2, metric.sample_count());
+        assert_eq!(Some(Duration::from_secs(60)), metric.value_duration());
+        assert!(!metric.on_target());
+    }
+
+    #[test]
+    fn reads_typed_values_after_reporting() {
+        let mut wait = Metric::with_target(
+            MetricType::AverageSpeedAnswer,
+            Target::mean_duration(Duration::from_secs(20)),
+        )
+        .unwrap();
+        let mut sla = Metric::with_target(
+            MetricType::ServiceLevel(Duration::from_secs(20)),
+            Target::percent(0.8),
+        )
+        .unwrap();
+        let mut answers = answer_count(0, None);
+        assert_eq!(None, wait.value_duration());
+        assert_eq!(None, sla.value_percent());
+        assert_eq!(Some(0), answers.value_count());
+
+        let requests = [10, 20, 30, 60].map(|wait| RequestData {
+            wait_time: Some(Duration::from_secs(wait)),
+            ..answered()
+        });
+        for request in &requests {
+            wait.report(request);
+            sla.report(request);
+            answers.report(request);
+        }
+
+        assert_eq!(Some(Duration::from_secs(30)), wait.value_duration());
+        assert_eq!(Some(0.5), sla.value_percent());
+        assert_eq!(Some(4), answers.value_count());
+        // Each accessor only reads its own kind of value
+        assert_eq!(None, wait.value_percent());
+        assert_eq!(None, sla.value_count());
+        assert_eq!(None, answers.value_duration());
+    }
+
+    #[test]
+    fn pre_queue_abandons_are_not_in_queue() {
+        let metric = |metric_type: MetricType, target: Target| {
+            let mut metric = Metric::with_target(metric_type, target).unwrap();
+            let requests = [
+                RequestData {
+                    wait_time: Some(Duration::from_secs(20)),
+                    ..answered()
+                },
+                RequestData {
+                    status: Status::Abandoned,
+                    wait_time: Some(Duration::ZERO),
+                    abandon_reason: Some(AbandonReason::PreQueue),
+                    ..answered()
+                },
+            ];
+            for request in &requests {
+                metric.report(request);
+            }
+            metric
+        };
+
+        let abandon_rate = metric(MetricType::AbandonRate, Target::percent(0.1));
+        let time_in_queue = metric(
+            MetricType::AverageTimeInQueue,
+            Target::mean_duration(Duration::from_secs(20)),
+        );
+
+        assert_eq!(Some(0.5), abandon_rate.value_percent());
+        assert_eq!(1, time_in_queue.sample_count());
+        assert_eq!(
+            Some(Duration::from_secs(20)),
+            time_in_queue.value_duration()
+        );
+    }
+
+    #[test]
+    fn transfer_rate_of_answered_originals() {
+        let mut metric =
+            Metric::with_target(MetricType::TransferRate, Target::percent(0.4)).unwrap();
+        let requests = [
+            answered(),
+            RequestData {
+                transferred_to: Some(1),
+                ..answered()
+            },
+            RequestData {
+                transfer_of: Some(0),
+                ..answered()
+            },
+            RequestData {
+                status: Status::Abandoned,
+                ..answered()
+            },
+        ];
+        for request in &requests {
+            metric.report(request);
+        }
+
+        assert_eq!(2, metric.sample_count());
+        assert_eq!("0.5", metric.to_string());
+        assert!(!metric.on_target());
+    }
+
+    #[test]
+    fn grouped_service_levels_are_independent() {
+        let vip = Attribute::new(0, None);
+        let standard = Attribute::new(1, None);
+        let request = |attribute: &Attribute, wait: u64| RequestData {
+            wait_time: Some(Duration::from_secs(wait)),
+            required_attributes: vec![attribute.clone()],
+            ..answered()
+        };
+        let sla = |window: u64, group: &Attribute, label: &str| {
+            Metric::with_target(
+                MetricType::ServiceLevel(Duration::from_secs(window)),
+                Target::percent(0.8),
+            )
+            .unwrap()
+            .with_group(vec![group.clone()], label)
+        };
+
+        let mut vip_sla = sla(10, &vip, "VIP");
+        let mut standard_sla = sla(30, &standard, "Standard");
+        // VIP requests within 10s, and standard requests between 10s and 30s
+        let requests = [
+            request(&vip, 5),
+            request(&vip, 8),
+            request(&standard, 20),
+            request(&standard, 25),
+            request(&standard, 40),
+        ];
+        for r in &requests {
+            vip_sla.report(r);
+            standard_sla.report(r);
+        }
+
+        assert_eq!("ServiceLevel(10s)[VIP]", vip_sla.name());
+        assert_eq!(2, vip_sla.sample_count());
+        assert_eq!("1", vip_sla.to_string());
+        assert!(vip_sla.on_target());
+
+        assert_eq!("ServiceLevel(30s)[Standard]", standard_sla.name());
+        assert_eq!(3, standard_sla.sample_count());
+        assert_eq!("0.6667", standard_sla.to_string());
+        assert!(!standard_sla.on_target());
+    }
+
+    #[test]
+    fn histogram_counts_answered_wait_times() {
+        let mut metric = Metric::wait_time_histogram(vec![
+            Duration::from_secs(20),
+            Duration::from_secs(10),
+            Duration::from_secs(30),
+        ]);
+
+        for secs in [0, 5, 10, 25, 30, 45, 90] {
+            metric.report(&RequestData {
+                wait_time: Some(Duration::from_secs(secs)),
+                ..answered()
+            });
+        }
+        metric.report(&RequestData {
+            status: Status::Abandoned,
+            ..answered()
+        });
+
+        assert_eq!(
+            "0s-10s: 2, 10s-20s: 1, 20s-30s: 1, 30s+: 3",
+            metric.to_string()
+        );
+        assert!(metric.on_target());
+
+        metric.reset();
+        assert_eq!(
+            "0s-10s: 0, 10s-20s: 0, 20s-30s: 0, 30s+: 0",
+            metric.to_string()
+        );
+    }
+
+    #[test]
+    fn accepts_percent_targets_in_range() {
+        for percent in [0.0, 0.5, 1.0] {
+            assert!(Metric::with_target(MetricType::AbandonRate, Target::percent(percent)).is_ok());
+        }
+    }
+
+    #[test]
+    fn rejects_percent_targets_out_of_range() {
+        let sla = MetricType::ServiceLevel(Duration::from_secs(20));
+
+        for percent in [-0.1, 1.5, f64::NAN] {
+            assert!(Metric::with_target(sla, Target::percent(percent)).is_err());
+            assert!(
+                Metric::with_target(MetricType::UtilisationTime, Target::percent(percent)).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_targets_at_setup() {
+        let histogram = Histogram::new(vec![Duration::from_secs(20)]);
+
+        assert!(Metric::with_target(MetricType::AnswerCount, Target::percent(0.5)).is_err());
+        assert!(Metric::with_target(MetricType::UtilisationTime, Target::count(1)).is_err());
+        assert!(
+            Metric::with_target(MetricType::WaitTimeHistogram, Value::Histogram(histogram))
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,243 @@
+use core::fmt::{Display, Formatter, Result};
+use core::time::Duration;
+use std::collections::BTreeMap;
+
+use crate::value::{write_decimal, Histogram};
+use crate::{Aggregator, Metric, Value};
+
+/// The mean of computed `Value`s over multiple runs.
+#[derive(Clone, Debug)]
+enum Mean {
+    Duration { sum: Duration, runs: u32 },
+    Float { sum: f64, runs: f64 },
+    Histogram { sum: Histogram, runs: u32 },
+}
+
+impl Display for Mean {
+    #[allow(clippy::cast_precision_loss)]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        match self {
+            Self::Duration { runs: 0, .. } | Self::Histogram { runs: 0, .. } => write!(f, "None"),
+            Self::Float { runs, .. } if *runs == 0f64 => write!(f, "None"),
+            Self::Duration { sum, runs } => write!(f, "{:?}", *sum / *runs),
+            Self::Float { sum, runs } => write_decimal(f, sum / runs),
+            Self::Histogram { sum, runs } => {
+                for (i, (label, count)) in sum.labels().iter().zip(&sum.counts).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{label}: {}", *count as f64 / f64::from(*runs))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Mean {
+    fn new(value: &Value) -> Self {
+        match value {
+            Value::MeanDuration(_) => Self::Duration {
+                sum: Duration::ZERO,
+                runs: 0,
+            },
+            Value::Count(_) | Value::Percent(_) => Self::Float {
+                sum: 0f64,
+                runs: 0f64,
+            },
+            Value::Histogram(h) => Self::Histogram {
+                sum: Histogram::new(h.bounds.clone()),
+                runs: 0,
+            },
+        }
+    }
+
+    /// Report the computed `value` of a single run. Runs without a computed value are skipped.
+    #[allow(clippy::cast_precision_loss)]
+    fn report(&mut self, value: &Value) {
+        match (self, value) {
+            (Self::Duration { sum, runs }, Value::MeanDuration(m)) if m.count > 0 => {
+                *sum += m.sum / m.count;
+                *runs += 1;
+            }
+            (Self::Float { sum, runs }, Value::Percent(m)) if m.count > 0f64 => {
+                *sum += m.sum / m.count;
+                *runs += 1f64;
+            }
+            (Self::Float { sum, runs }, Value::Count(m)) => {
+                *sum += m.count as f64;
+                *runs += 1f64;
+            }
+            (Self::Histogram { sum, runs }, Value::Histogram(m)) => {
+                for (total, count) in sum.counts.iter_mut().zip(&m.counts) {
+                    *total += count;
+                }
+                *runs += 1;
+            }
+            _ => (),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    mean: Mean,
+    on_target: usize,
+}
+
+/// A summary of the `Aggregator`s of multiple simulation runs. Each metric is summarised as the
+/// mean of its computed value per run, along with how many runs were on target.
+#[derive(Default, Clone, Debug)]
+pub struct Summary {
+    metrics: BTreeMap<String, Entry>,
+    simulations: usize,
+    passed: usize,
+}
+
+/// A header line with the number of simulations, then a line for each metric in name order.
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        writeln!(f, "Summary of {} simulations", self.simulations)?;
+        for (name, entry) in &self.metrics {
+            writeln!(
+                f,
+                "{:20} {:>5}/{:<5} {}",
+                name, entry.on_target, self.simulations, entry.mean,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary {
+    /// Add the results of a single simulation run to the `Summary`.
+    pub fn push(&mut self, aggregator: &Aggregator) {
+        self.simulations += 1;
+        if aggregator.all_on_target() {
+            self.passed += 1;
+        }
+        for metric in aggregator.metrics() {
+            self.report(metric);
+        }
+    }
+
+    fn report(&mut self, metric: &Metric) {
+        let entry = self
+            .metrics
+            .entry(metric.name().to_owned())
+            .or_insert_with(|| Entry {
+                mean: Mean::new(metric.value()),
+                on_target: 0,
+            });
+
+        entry.mean.report(metric.value());
+        if metric.on_target() {
+            entry.on_target += 1;
+        }
+    }
+
+    /// The number of simulations in this `Summary`.
+    #[must_use]
+    pub fn simulations(&self) -> usize {
+        self.simulations
+    }
+
+    /// Returns whether every metric was on target in every simulation.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.passed == self.simulations
+    }
+}
+
+impl<'a> FromIterator<&'a Aggregator> for Summary {
+    fn from_iter<T: IntoIterator<Item = &'a Aggregator>>(iter: T) -> Self {
+        let mut summary = Self::default();
+        for aggregator in iter {
+            summary.push(aggregator);
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::answered;
+    use crate::{MetricType, Target};
+    use awt_simulation::request::{Data, Status};
+
+    fn request(status: Status, wait_time: u64) -> Data {
+        Data {
+            status,
+            wait_time: Some(Duration::from_secs(wait_time)),
+            handle_time: None,
+            clean_up_time: None,
+            served_by: None,
+            ..answered()
+        }
+    }
+
+    fn aggregator(data: &[Data]) -> Aggregator {
+        let metrics = [
+            Metric::with_target(MetricType::AbandonRate, Target::percent(0.5)).unwrap(),
+            Metric::with_target(
+                MetricType::AverageTimeInQueue,
+                Target::mean_duration(Duration::from_secs(10)),
+            )
+            .unwrap(),
+        ];
+        let mut aggregator = Aggregator::with_metrics(&metrics);
+        aggregator.calculate(data).unwrap();
+        aggregator
+    }
+
+    #[test]
+    fn averages_runs() {
+        let runs = [
+            aggregator(&[request(Status::Abandoned, 10)]),
+            aggregator(&[request(Status::Answered, 20), request(Status::Answered, 20)]),
+        ];
+
+        let summary = runs.iter().collect::<Summary>();
+
+        assert_eq!(2, summary.simulations());
+        assert!(!summary.passed());
+        let output = format!("{summary}");
+        assert!(output.contains("AbandonRate              1/2     0.5"));
+        assert!(output.contains("AverageTimeInQueue       1/2     15s"));
+    }
+
+    #[test]
+    fn lists_metrics_in_name_order() {
+        let run = aggregator(&[request(Status::Answered, 10)]);
+        let summary = [run].iter().collect::<Summary>();
+
+        assert_eq!(
+            "\
+Summary of 1 simulations
+AbandonRate              1/1     0
+AverageTimeInQueue       1/1     10s
+",
+            summary.to_string()
+        );
+    }
+
+    #[test]
+    fn passes_when_all_on_target() {
+        let runs = [
+            aggregator(&[request(Status::Answered, 10)]),
+            aggregator(&[request(Status::Answered, 5)]),
+        ];
+
+        assert!(runs.iter().all(Aggregator::all_on_target));
+        assert!(runs.iter().collect::<Summary>().passed());
+    }
+
+    #[test]
+    fn missed_target_fails() {
+        let missed = aggregator(&[request(Status::Answered, 20)]);
+
+        assert!(!missed.all_on_target());
+        assert!(![missed].iter().collect::<Summary>().passed());
+    }
+}
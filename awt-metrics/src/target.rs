@@ -13,6 +13,7 @@ pub enum TargetCondition {
 pub type Target = Value;
 
 impl Target {
+    #[must_use]
     pub fn mean_duration(duration: Duration) -> Self {
         Self::MeanDuration(MeanDuration {
             sum: duration,
@@ -20,10 +21,12 @@ impl Target {
         })
     }
 
+    #[must_use]
     pub fn count(count: usize) -> Self {
         Self::Count(Count { count })
     }
 
+    #[must_use]
     pub fn percent(percent: f64) -> Self {
         Self::Percent(Percent {
             sum: percent,
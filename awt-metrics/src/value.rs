@@ -1,12 +1,16 @@
+use alloc::{string::String, vec, vec::Vec};
 use core::cmp::Ordering;
 use core::time::Duration;
 use core::{fmt, fmt::Display, fmt::Formatter};
 
+use crate::MetricError;
+
 #[derive(Clone, Debug)]
 pub enum Value {
     MeanDuration(MeanDuration),
     Count(Count),
     Percent(Percent),
+    Histogram(Histogram),
 }
 
 impl Display for Value {
@@ -15,6 +19,7 @@ impl Display for Value {
             Self::MeanDuration(a) => write!(f, "{a}"),
             Self::Count(a) => write!(f, "{a}"),
             Self::Percent(a) => write!(f, "{a}"),
+            Self::Histogram(a) => write!(f, "{a}"),
         }
     }
 }
@@ -36,24 +41,81 @@ impl PartialEq for Value {
             (Value::MeanDuration(a), Value::MeanDuration(b)) => a == b,
             (Value::Count(a), Value::Count(b)) => a == b,
             (Value::Percent(a), Value::Percent(b)) => a == b,
+            (Value::Histogram(a), Value::Histogram(b)) => a == b,
             _ => false,
         }
     }
 }
 
 impl Value {
+    #[must_use]
     pub fn default_mean_duration() -> Self {
         Self::MeanDuration(MeanDuration::default())
     }
+    #[must_use]
     pub fn default_count() -> Self {
         Self::Count(Count::default())
     }
+    #[must_use]
     pub fn default_percent() -> Self {
         Self::Percent(Percent::default())
     }
+
+    /// The value as a number: seconds for a `MeanDuration`, the count for a `Count`, and the
+    /// ratio for a `Percent`. `None` when nothing has been reported.
+    #[allow(clippy::cast_precision_loss)]
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::MeanDuration(m) => m.mean().map(|mean| mean.as_secs_f64()),
+            Self::Count(c) => Some(c.count as f64),
+            Self::Percent(p) => p.ratio(),
+            Self::Histogram(_) => None,
+        }
+    }
+
+    /// Combines `other` into this value as if its samples had been reported here, e.g. the
+    /// values of the same metric from simulations run elsewhere. Means and percents are weighted
+    /// by their counts, counts are summed, and histogram buckets are summed.
+    ///
+    /// # Errors
+    ///
+    /// Will error if `other` is a different kind of `Value`, or a `Histogram` with different
+    /// bounds. The value is left unchanged on error.
+    pub fn merge(&mut self, other: &Value) -> Result<(), MetricError> {
+        match (self, other) {
+            (Self::MeanDuration(a), Self::MeanDuration(b)) => {
+                a.sum += b.sum;
+                a.count += b.count;
+            }
+            (Self::Count(a), Self::Count(b)) => a.count += b.count,
+            (Self::Percent(a), Self::Percent(b)) => {
+                a.sum += b.sum;
+                a.count += b.count;
+            }
+            (Self::Histogram(a), Self::Histogram(b)) if a.bounds == b.bounds => {
+                for (count, other) in a.counts.iter_mut().zip(&b.counts) {
+                    *count += other;
+                }
+            }
+            _ => return Err(MetricError {}),
+        }
+        Ok(())
+    }
+
+    /// Resets to the default of the same kind of `Value`.
+    pub fn reset(&mut self) {
+        *self = match self {
+            Self::MeanDuration(_) => Self::default_mean_duration(),
+            Self::Count(_) => Self::default_count(),
+            Self::Percent(_) => Self::default_percent(),
+            Self::Histogram(h) => Self::Histogram(Histogram::new(h.bounds.clone())),
+        };
+    }
 }
 
-// MeanDuration Counters are used to provide a Mean of the provided Duration values.
+// MeanDuration Counters are used to provide a Mean of the provided Duration values. Without any
+// values there is no mean, which orders before any mean.
 #[derive(Clone, Debug, Default, Eq)]
 pub struct MeanDuration {
     pub sum: Duration,
@@ -62,17 +124,16 @@ pub struct MeanDuration {
 
 impl Display for MeanDuration {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if self.count == 0 {
-            return write!(f, "None");
+        match self.mean() {
+            Some(mean) => write!(f, "{mean:?}"),
+            None => write!(f, "None"),
         }
-
-        write!(f, "{:?}", self.sum / self.count)
     }
 }
 
 impl Ord for MeanDuration {
     fn cmp(&self, other: &Self) -> Ordering {
-        (self.sum / self.count).cmp(&(other.sum / other.count))
+        self.mean().cmp(&other.mean())
     }
 }
 
@@ -84,11 +145,17 @@ impl PartialOrd for MeanDuration {
 
 impl PartialEq for MeanDuration {
     fn eq(&self, other: &Self) -> bool {
-        (self.sum / self.count) == (other.sum / other.count)
+        self.mean() == other.mean()
     }
 }
 
 impl MeanDuration {
+    /// The mean of the reported values, or `None` when nothing has been reported.
+    #[must_use]
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| self.sum / self.count)
+    }
+
     pub fn report(&mut self, duration: Duration) {
         self.count += 1;
         self.sum += duration;
@@ -130,35 +197,54 @@ impl Count {
     pub fn report(&mut self) {
         self.count += 1;
     }
+
+    /// Keeps the largest value reported.
+    pub fn report_max(&mut self, value: usize) {
+        self.count = self.count.max(value);
+    }
 }
 
 // Percentable is a count of matching values against a total available
 // Report: Position and negative. e.g. Things that in bounds, and things that
 //         out of bounds
 //         report_bool(value: bool)
+// Without any count there is no ratio, which orders before any ratio.
 #[derive(Clone, Debug, Default)]
 pub struct Percent {
     pub sum: f64,
     pub count: f64,
 }
 
+/// The ratio to at most 4 decimal places, e.g. `0.3333`, or `0.75`. A precision such as `{:.2}`
+/// writes exactly that many decimal places instead. `ratio()` has the raw value.
 impl Display for Percent {
-    #[allow(clippy::cast_precision_loss)]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        if self.count == 0f64 {
-            return write!(f, "None");
+        match self.ratio() {
+            Some(ratio) => write_decimal(f, ratio),
+            None => write!(f, "None"),
         }
+    }
+}
 
-        write!(f, "{}", self.sum / self.count)
+/// Writes `value` with the formatter's precision, otherwise to at most 4 decimal places without
+/// trailing zeros.
+pub(crate) fn write_decimal(f: &mut Formatter<'_>, value: f64) -> fmt::Result {
+    if let Some(precision) = f.precision() {
+        return write!(f, "{value:.precision$}");
     }
+
+    let fixed = format!("{value:.4}");
+    let trimmed = fixed.trim_end_matches('0').trim_end_matches('.');
+    write!(f, "{trimmed}")
 }
 
 impl Ord for Percent {
     fn cmp(&self, other: &Self) -> Ordering {
-        // we use partial_cmp since f64 doesn't implement Ord
-        (self.sum / self.count)
-            .partial_cmp(&(other.sum / other.count))
-            .unwrap()
+        // total_cmp since f64 doesn't implement Ord
+        match (self.ratio(), other.ratio()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => a.is_some().cmp(&b.is_some()),
+        }
     }
 }
 
@@ -170,17 +256,180 @@ impl PartialOrd for Percent {
 
 impl PartialEq for Percent {
     fn eq(&self, other: &Self) -> bool {
-        self.sum == other.sum && self.count == other.count
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl Eq for Percent {}
 
 impl Percent {
+    /// The ratio of `sum` to `count`, or `None` when the count is zero.
+    #[must_use]
+    pub fn ratio(&self) -> Option<f64> {
+        (self.count != 0f64).then(|| self.sum / self.count)
+    }
+
     pub fn report(&mut self, in_range: bool) {
         if in_range {
             self.sum += 1f64;
         }
         self.count += 1f64;
     }
+
+    /// Report a `part` of a `whole`, e.g. the time spent busy out of the time available.
+    pub fn report_ratio(&mut self, part: f64, whole: f64) {
+        self.sum += part;
+        self.count += whole;
+    }
+}
+
+// Histogram counts durations into buckets. Each bound is the exclusive upper bound of a bucket,
+// with a final bucket for everything from the largest bound.
+// Report: report(duration)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Histogram {
+    pub bounds: Vec<Duration>,
+    pub counts: Vec<usize>,
+}
+
+/// Each bucket with its count, e.g. `0s-10s: 3, 10s-20s: 1, 20s+: 0`.
+impl Display for Histogram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (label, count)) in self.labels().iter().zip(&self.counts).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{label}: {count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Histogram {
+    /// A `Histogram` with buckets split at each of `bounds`.
+    #[must_use]
+    pub fn new(mut bounds: Vec<Duration>) -> Self {
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+        }
+    }
+
+    pub fn report(&mut self, duration: Duration) {
+        let bucket = self.bounds.partition_point(|bound| *bound <= duration);
+        self.counts[bucket] += 1;
+    }
+
+    /// The range of each bucket, e.g. `10s-20s`, with the last bucket open ended, e.g. `20s+`.
+    #[must_use]
+    pub fn labels(&self) -> Vec<String> {
+        // `Duration::ZERO` debugs as `0ns`, which reads oddly next to the other bounds
+        let lowers = core::iter::once(None).chain(self.bounds.iter().map(Some));
+        let uppers = self.bounds.iter().map(Some).chain(core::iter::once(None));
+
+        lowers
+            .zip(uppers)
+            .map(|(lower, upper)| match (lower, upper) {
+                (None, Some(upper)) => format!("0s-{upper:?}"),
+                (Some(lower), Some(upper)) => format!("{lower:?}-{upper:?}"),
+                (Some(lower), None) => format!("{lower:?}+"),
+                (None, None) => "0s+".into(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_mean_duration_orders_first() {
+        let empty = MeanDuration::default();
+        let zero = MeanDuration {
+            sum: Duration::ZERO,
+            count: 1,
+        };
+
+        assert_eq!(None, empty.mean());
+        assert!(empty < zero);
+        assert_eq!(empty, MeanDuration::default());
+        assert_ne!(empty, zero);
+    }
+
+    #[test]
+    fn empty_percent_orders_first() {
+        let empty = Percent::default();
+        let zero = Percent {
+            sum: 0.0,
+            count: 1.0,
+        };
+
+        assert_eq!(None, empty.ratio());
+        assert!(empty < zero);
+        assert_eq!(empty, Percent::default());
+        assert_ne!(empty, zero);
+    }
+
+    #[test]
+    fn percents_compare_by_ratio() {
+        let half = Percent {
+            sum: 1.0,
+            count: 2.0,
+        };
+        let also_half = Percent {
+            sum: 2.0,
+            count: 4.0,
+        };
+
+        assert_eq!(half, also_half);
+        assert_eq!(Some(0.5), Value::Percent(half).as_f64());
+    }
+
+    #[test]
+    fn merges_percents_weighted_by_count() {
+        let mut merged = Value::Percent(Percent {
+            sum: 3.0,
+            count: 4.0,
+        });
+        let other = Value::Percent(Percent {
+            sum: 1.0,
+            count: 6.0,
+        });
+
+        assert!(merged.merge(&other).is_ok());
+        assert_eq!(Some(0.4), merged.as_f64());
+    }
+
+    #[test]
+    fn rejects_merging_mismatched_values() {
+        let mut count = Value::default_count();
+        assert!(count.merge(&Value::default_percent()).is_err());
+
+        let mut histogram = Value::Histogram(Histogram::new(vec![Duration::from_secs(10)]));
+        let other = Value::Histogram(Histogram::new(vec![Duration::from_secs(20)]));
+        assert!(histogram.merge(&other).is_err());
+    }
+
+    #[test]
+    fn displays_percent_to_four_places() {
+        let third = Percent {
+            sum: 1.0,
+            count: 3.0,
+        };
+
+        assert_eq!("0.3333", third.to_string());
+        assert_eq!("0.33", format!("{third:.2}"));
+        assert_eq!(Some(1.0 / 3.0), third.ratio());
+
+        let whole = Percent {
+            sum: 2.0,
+            count: 2.0,
+        };
+        assert_eq!("1", whole.to_string());
+        assert_eq!("1.00", format!("{whole:.2}"));
+    }
 }
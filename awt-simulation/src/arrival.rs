@@ -0,0 +1,204 @@
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+use rand::Rng;
+
+use crate::Client;
+
+/// How the `start` of each `Request` is spread across the `Simulation`.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArrivalPattern {
+    /// Equally likely to arrive at any point.
+    #[default]
+    Uniform,
+    /// A piecewise arrival rate. Each `(fraction, weight)` starts a segment at that fraction of the
+    /// `Simulation` (`0.0..=1.0`), running until the next segment or the end. Arrivals within a
+    /// segment are uniform, with each segment as likely as its weight relative to the others. e.g.
+    /// `[(0.0, 3.0), (0.5, 1.0)]` has three times the arrivals in the first half as the second.
+    /// Nothing arrives before the first segment.
+    Piecewise(Vec<(f64, f64)>),
+}
+
+impl ArrivalPattern {
+    /// Sample an arrival in `start..=end`. A `Piecewise` pattern without any positive weight is
+    /// sampled as `Uniform`.
+    pub fn sample<R: Rng + ?Sized>(&self, start: Duration, end: Duration, rng: &mut R) -> Duration {
+        let Self::Piecewise(points) = self else {
+            return rng.gen_range(start..=end);
+        };
+
+        let mut points: Vec<(f64, f64)> = points
+            .iter()
+            .map(|(fraction, weight)| (fraction.clamp(0.0, 1.0), weight.max(0.0)))
+            .collect();
+        points.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Each segment as (lower fraction, upper fraction, weight)
+        let uppers = points.iter().skip(1).map(|(fraction, _)| *fraction);
+        let segments: Vec<(f64, f64, f64)> = points
+            .iter()
+            .zip(uppers.chain(core::iter::once(1.0)))
+            .map(|(&(lower, weight), upper)| (lower, upper, weight))
+            .collect();
+
+        let total: f64 = segments
+            .iter()
+            .map(|(lower, upper, weight)| (upper - lower) * weight)
+            .sum();
+        if total <= 0.0 {
+            return rng.gen_range(start..=end);
+        }
+
+        let mut roll = rng.gen_range(0.0..total);
+        for (lower, upper, weight) in segments {
+            let mass = (upper - lower) * weight;
+            if roll < mass {
+                return start + (end - start).mul_f64(lower + roll / weight);
+            }
+            roll -= mass;
+        }
+
+        // Only reachable through floating point rounding
+        end
+    }
+}
+
+/// Delivers requests in batches rather than individually, e.g. emails which are fetched every few
+/// minutes. Requests from every `Client` with the same `Batch` are delivered together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Batch {
+    /// The most requests delivered at once. A size of 0 is treated as 1.
+    pub size: usize,
+    /// The time between deliveries.
+    pub interval: Duration,
+}
+
+impl Batch {
+    /// The tick each of `arrivals` is delivered at. In order of arrival, up to `size` requests are
+    /// delivered at each multiple of `interval` after `start`, once they have all arrived. Any
+    /// requests which would be delivered after `end` are delivered at `end`.
+    #[must_use]
+    pub fn deliver(&self, arrivals: &[Duration], start: Duration, end: Duration) -> Vec<Duration> {
+        let mut order: Vec<usize> = (0..arrivals.len()).collect();
+        order.sort_by_key(|index| arrivals[*index]);
+
+        let mut delivered = vec![end; arrivals.len()];
+        let mut previous: Option<Duration> = None;
+        for batch in order.chunks(self.size.max(1)) {
+            let last = arrivals[batch[batch.len() - 1]].max(start);
+            let mut tick = self
+                .round_up(last - start)
+                .map_or(end, |offset| start + offset);
+            if let Some(previous) = previous {
+                tick = tick.max(previous.saturating_add(self.interval));
+            }
+            let tick = tick.min(end);
+
+            for index in batch {
+                delivered[*index] = tick;
+            }
+            previous = Some(tick);
+        }
+
+        delivered
+    }
+
+    /// Rounds `offset` up to a multiple of `interval`, or `None` if that overflows.
+    fn round_up(&self, offset: Duration) -> Option<Duration> {
+        let interval = self.interval.as_nanos();
+        if interval == 0 {
+            return Some(offset);
+        }
+
+        let nanos = offset.as_nanos().div_ceil(interval) * interval;
+        u64::try_from(nanos).ok().map(Duration::from_nanos)
+    }
+}
+
+/// Replaces the sampled `starts` of requests from `clients` with a `Batch` by the tick they are
+/// delivered at.
+pub(crate) fn batch_arrivals(
+    clients: &[Client],
+    starts: &mut [Duration],
+    start: Duration,
+    end: Duration,
+) {
+    let mut batches: Vec<(Batch, Vec<usize>)> = Vec::new();
+    for (index, client) in clients.iter().enumerate() {
+        let Some(batch) = client.batch else {
+            continue;
+        };
+        match batches.iter_mut().find(|(b, _)| *b == batch) {
+            Some((_, indices)) => indices.push(index),
+            None => batches.push((batch, vec![index])),
+        }
+    }
+
+    for (batch, indices) in batches {
+        let arrivals: Vec<Duration> = indices.iter().map(|index| starts[*index]).collect();
+        for (index, tick) in indices.iter().zip(batch.deliver(&arrivals, start, end)) {
+            starts[*index] = tick;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    const ONE_HOUR: Duration = Duration::new(3600, 0);
+    const HALF_HOUR: Duration = Duration::new(1800, 0);
+    const SAMPLES: usize = 10_000;
+
+    fn early(pattern: &ArrivalPattern) -> usize {
+        let mut rng = SmallRng::seed_from_u64(0);
+        (0..SAMPLES)
+            .map(|_| pattern.sample(Duration::ZERO, ONE_HOUR, &mut rng))
+            .inspect(|arrival| assert!(*arrival <= ONE_HOUR))
+            .filter(|arrival| *arrival < HALF_HOUR)
+            .count()
+    }
+
+    #[test]
+    fn uniform_is_even() {
+        let early = early(&ArrivalPattern::Uniform);
+        assert!((4_700..5_300).contains(&early), "early arrivals {early}");
+    }
+
+    #[test]
+    fn front_loaded_arrives_early() {
+        let pattern = ArrivalPattern::Piecewise(vec![(0.5, 1.0), (0.0, 9.0)]);
+
+        // Expected 9/10 of arrivals in the first half
+        let early = early(&pattern);
+        assert!((8_700..9_300).contains(&early), "early arrivals {early}");
+    }
+
+    #[test]
+    fn without_weight_is_uniform() {
+        let early = early(&ArrivalPattern::Piecewise(vec![(0.0, 0.0)]));
+        assert!((4_700..5_300).contains(&early), "early arrivals {early}");
+    }
+
+    #[test]
+    fn batches_are_delivered_in_order_of_arrival() {
+        let batch = Batch {
+            size: 2,
+            interval: Duration::from_secs(60),
+        };
+        let arrivals = [40, 10, 20, 30, 3590, 3595].map(Duration::from_secs);
+
+        let delivered: Vec<u64> = batch
+            .deliver(&arrivals, Duration::ZERO, ONE_HOUR)
+            .iter()
+            .map(Duration::as_secs)
+            .collect();
+
+        // Only two of the first minute's arrivals fit in its delivery, so the rest wait for the
+        // next. The last batch would be delivered after the end.
+        assert_eq!(vec![120, 60, 60, 120, 3600, 3600], delivered);
+    }
+}
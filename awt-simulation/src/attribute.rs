@@ -1,4 +1,5 @@
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Attribute {
     pub id: u64,
     pub level: Option<usize>,
@@ -9,4 +10,67 @@ impl Attribute {
     pub fn new(id: u64, level: Option<usize>) -> Self {
         Self { id, level }
     }
+
+    /// Returns whether this `Attribute` satisfies the `required` attribute. The ids must match,
+    /// and when a level is required this `Attribute` must have at least that level.
+    #[must_use]
+    pub fn satisfies(&self, required: &Attribute) -> bool {
+        self.id == required.id
+            && match (self.level, required.level) {
+                (_, None) => true,
+                (Some(level), Some(required)) => level >= required,
+                (None, Some(_)) => false,
+            }
+    }
+
+    /// Returns whether every `required` attribute is satisfied by at least one of `attributes`.
+    #[must_use]
+    pub fn satisfies_all(attributes: &[Attribute], required: &[Attribute]) -> bool {
+        required
+            .iter()
+            .all(|r| attributes.iter().any(|a| a.satisfies(r)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatched_id_does_not_satisfy() {
+        assert!(!Attribute::new(1, None).satisfies(&Attribute::new(2, None)));
+        assert!(!Attribute::new(1, Some(5)).satisfies(&Attribute::new(2, Some(1))));
+    }
+
+    #[test]
+    fn any_level_satisfies_no_required_level() {
+        assert!(Attribute::new(1, None).satisfies(&Attribute::new(1, None)));
+        assert!(Attribute::new(1, Some(1)).satisfies(&Attribute::new(1, None)));
+    }
+
+    #[test]
+    fn compares_levels() {
+        let required = Attribute::new(1, Some(2));
+
+        assert!(!Attribute::new(1, None).satisfies(&required));
+        assert!(!Attribute::new(1, Some(1)).satisfies(&required));
+        assert!(Attribute::new(1, Some(2)).satisfies(&required));
+        assert!(Attribute::new(1, Some(3)).satisfies(&required));
+    }
+
+    #[test]
+    fn satisfies_all_requirements() {
+        let attributes = [Attribute::new(1, Some(2)), Attribute::new(2, None)];
+
+        assert!(Attribute::satisfies_all(&attributes, &[]));
+        assert!(Attribute::satisfies_all(&[], &[]));
+        assert!(Attribute::satisfies_all(
+            &attributes,
+            &[Attribute::new(1, Some(1)), Attribute::new(2, None)]
+        ));
+        assert!(!Attribute::satisfies_all(
+            &attributes,
+            &[Attribute::new(1, Some(1)), Attribute::new(3, None)]
+        ));
+    }
 }
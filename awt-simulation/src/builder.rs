@@ -0,0 +1,171 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::time::Duration;
+use rand::RngCore;
+
+use crate::client::Client;
+use crate::end_condition::EndCondition;
+use crate::error::Error;
+use crate::routing::RoutingStrategy;
+use crate::server::Server;
+use crate::tick_mode::TickMode;
+use crate::{Result, Simulation};
+
+/// Fluently constructs a `Simulation`, validating the setup in `build()`.
+#[allow(clippy::module_name_repetitions)]
+pub struct SimulationBuilder {
+    end: Duration,
+    tick_size: Duration,
+    clients: Vec<Client>,
+    servers: Vec<Server>,
+    routing_strategy: Option<Box<dyn RoutingStrategy>>,
+    tick_mode: TickMode,
+    end_condition: Option<EndCondition>,
+    record_occupancy: bool,
+    record_routes: bool,
+    record_queue_depth: bool,
+    arrival_seed: Option<u64>,
+    service_seed: Option<u64>,
+    rng: Box<dyn RngCore + Send>,
+}
+
+impl core::fmt::Debug for SimulationBuilder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SimulationBuilder")
+            .field("end", &self.end)
+            .field("tick_size", &self.tick_size)
+            .field("clients", &self.clients)
+            .field("servers", &self.servers)
+            .field("tick_mode", &self.tick_mode)
+            .field("end_condition", &self.end_condition)
+            .field("record_occupancy", &self.record_occupancy)
+            .field("record_routes", &self.record_routes)
+            .field("record_queue_depth", &self.record_queue_depth)
+            .field("arrival_seed", &self.arrival_seed)
+            .field("service_seed", &self.service_seed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SimulationBuilder {
+    #[must_use]
+    pub fn new(end: Duration, tick_size: Duration, rng: Box<dyn RngCore + Send>) -> Self {
+        Self {
+            end,
+            tick_size,
+            clients: Vec::new(),
+            servers: Vec::new(),
+            routing_strategy: None,
+            tick_mode: TickMode::default(),
+            end_condition: None,
+            record_occupancy: false,
+            record_routes: false,
+            record_queue_depth: false,
+            arrival_seed: None,
+            service_seed: None,
+            rng,
+        }
+    }
+
+    /// Add `clients` to the `Simulation`.
+    #[must_use]
+    pub fn clients(mut self, mut clients: Vec<Client>) -> Self {
+        self.clients.append(&mut clients);
+        self
+    }
+
+    /// Add `servers` to the `Simulation`.
+    #[must_use]
+    pub fn servers(mut self, mut servers: Vec<Server>) -> Self {
+        self.servers.append(&mut servers);
+        self
+    }
+
+    /// Set the `RoutingStrategy`. Default is `Greedy`.
+    #[must_use]
+    pub fn routing_strategy(mut self, strategy: Box<dyn RoutingStrategy>) -> Self {
+        self.routing_strategy = Some(strategy);
+        self
+    }
+
+    /// Set how the `Simulation` advances between ticks. Default is `TickMode::EventDriven`.
+    #[must_use]
+    pub fn tick_mode(mut self, tick_mode: TickMode) -> Self {
+        self.tick_mode = tick_mode;
+        self
+    }
+
+    /// Set when the `Simulation` stops. Default is `EndCondition::AtTick` the `end`.
+    #[must_use]
+    pub fn end_condition(mut self, end_condition: EndCondition) -> Self {
+        self.end_condition = Some(end_condition);
+        self
+    }
+
+    /// Record the occupancy series. Default is off.
+    #[must_use]
+    pub fn record_occupancy(mut self, record: bool) -> Self {
+        self.record_occupancy = record;
+        self
+    }
+
+    /// Record the routes which are handled. Default is off.
+    #[must_use]
+    pub fn record_routes(mut self, record: bool) -> Self {
+        self.record_routes = record;
+        self
+    }
+
+    /// Record the queue depth series. Default is off.
+    #[must_use]
+    pub fn record_queue_depth(mut self, record: bool) -> Self {
+        self.record_queue_depth = record;
+        self
+    }
+
+    /// Seed the arrival stream. Default is derived from the rng.
+    #[must_use]
+    pub fn arrival_seed(mut self, seed: u64) -> Self {
+        self.arrival_seed = Some(seed);
+        self
+    }
+
+    /// Seed the service (abandon time) stream. Default is derived from the rng.
+    #[must_use]
+    pub fn service_seed(mut self, seed: u64) -> Self {
+        self.service_seed = Some(seed);
+        self
+    }
+
+    /// Build the `Simulation`, ready to be enabled.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `tick_size` is zero.
+    pub fn build(self) -> Result<Simulation> {
+        if self.tick_size.is_zero() {
+            return Err(Error::ZeroTickSize);
+        }
+
+        let mut sim = Simulation::new(self.end, self.tick_size, self.rng);
+        sim.add_clients(self.clients)?;
+        sim.add_servers(self.servers)?;
+        sim.set_tick_mode(self.tick_mode)?;
+        if let Some(end_condition) = self.end_condition {
+            sim.set_end_condition(end_condition)?;
+        }
+        sim.set_record_occupancy(self.record_occupancy)?;
+        sim.set_record_routes(self.record_routes)?;
+        sim.set_record_queue_depth(self.record_queue_depth)?;
+        if let Some(seed) = self.arrival_seed {
+            sim.set_arrival_seed(seed)?;
+        }
+        if let Some(seed) = self.service_seed {
+            sim.set_service_seed(seed)?;
+        }
+        if let Some(strategy) = self.routing_strategy {
+            sim.set_routing_strategy(strategy)?;
+        }
+
+        Ok(sim)
+    }
+}
@@ -0,0 +1,235 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::time::Duration;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+use crate::end_condition::EndCondition;
+use crate::error::Error;
+use crate::request::{queue::Queue as RequestQueue, Request};
+use crate::routing::RoutingStrategy;
+use crate::server::{
+    queue::{Queue as ServerQueue, Queued},
+    QueueableServer,
+};
+use crate::tick_mode::TickMode;
+use crate::{Client, Result, Simulation};
+
+/// The state of a running `Simulation`, taken with `Simulation::checkpoint()`. A `Simulation` is
+/// restored from it with `Simulation::restore()`, and continues ticking from the same tick. With
+/// the `serde` feature, a `Checkpoint` can be serialized, e.g. to resume a long simulation after
+/// it is preempted.
+///
+/// The `RoutingStrategy` is not part of a `Checkpoint`, so it is provided again when restoring.
+/// Any state the strategy keeps between ticks starts afresh.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    start: Duration,
+    tick: Duration,
+    tick_size: Duration,
+    tick_mode: TickMode,
+    end: Duration,
+    end_condition: EndCondition,
+    running: bool,
+    clients: Vec<Client>,
+    requests: Vec<Request>,
+    // Ids of the requests which completed since they were last taken
+    completed: Vec<usize>,
+    servers: Vec<(QueueableServer, Queued)>,
+    record_occupancy: bool,
+    occupancy: Vec<(Duration, usize)>,
+    record_routes: bool,
+    routes: Vec<(Duration, usize, usize)>,
+    record_queue_depth: bool,
+    queue_depth: Vec<(Duration, usize)>,
+    peak_queue_depth: usize,
+    stalled: usize,
+    arrival_seed: Option<u64>,
+    service_seed: Option<u64>,
+    drawn_seeds: Option<(u64, u64)>,
+    // The seed the rng continues from
+    rng_seed: u64,
+}
+
+impl Checkpoint {
+    /// The tick the `Simulation` was at when the `Checkpoint` was taken.
+    #[must_use]
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+}
+
+impl Simulation {
+    /// Takes a `Checkpoint` of the running `Simulation`. The rng is reseeded from itself, with the
+    /// seed kept in the `Checkpoint`, so that this `Simulation` and any restored from the
+    /// `Checkpoint` continue identically.
+    ///
+    /// # Errors
+    ///
+    /// Will error if the `Simulation` is not running, i.e. not enabled or already complete.
+    pub fn checkpoint(&mut self) -> Result<Checkpoint> {
+        if !self.running {
+            return Err(Error::NotRunning);
+        }
+
+        let rng_seed = self.rng.gen();
+        self.rng = Box::new(SmallRng::seed_from_u64(rng_seed));
+
+        Ok(Checkpoint {
+            start: self.start,
+            tick: self.tick,
+            tick_size: self.tick_size,
+            tick_mode: self.tick_mode,
+            end: self.end,
+            end_condition: self.end_condition,
+            running: self.running,
+            clients: self.clients.clone(),
+            requests: self
+                .request_queue
+                .requests()
+                .iter()
+                .map(|request| request.borrow().clone())
+                .collect(),
+            completed: self.request_queue.completed_ids(),
+            servers: self.server_queue.snapshot(),
+            record_occupancy: self.record_occupancy,
+            occupancy: self.occupancy.clone(),
+            record_routes: self.record_routes,
+            routes: self.routes.clone(),
+            record_queue_depth: self.record_queue_depth,
+            queue_depth: self.queue_depth.clone(),
+            peak_queue_depth: self.peak_queue_depth,
+            stalled: self.stalled,
+            arrival_seed: self.arrival_seed,
+            service_seed: self.service_seed,
+            drawn_seeds: self.drawn_seeds,
+            rng_seed,
+        })
+    }
+
+    /// Restores a running `Simulation` from `checkpoint`, which routes with `routing_strategy`.
+    /// Ids generated from then on are greater than those in the `checkpoint`.
+    #[must_use]
+    pub fn restore(checkpoint: Checkpoint, routing_strategy: Box<dyn RoutingStrategy>) -> Self {
+        Self {
+            start: checkpoint.start,
+            tick: checkpoint.tick,
+            tick_size: checkpoint.tick_size,
+            tick_mode: checkpoint.tick_mode,
+            end: checkpoint.end,
+            end_condition: checkpoint.end_condition,
+            running: checkpoint.running,
+            clients: checkpoint.clients,
+            request_queue: RequestQueue::restore(checkpoint.requests, &checkpoint.completed),
+            server_queue: ServerQueue::restore(checkpoint.servers),
+            routing_strategy,
+            record_occupancy: checkpoint.record_occupancy,
+            occupancy: checkpoint.occupancy,
+            record_routes: checkpoint.record_routes,
+            routes: checkpoint.routes,
+            record_queue_depth: checkpoint.record_queue_depth,
+            queue_depth: checkpoint.queue_depth,
+            peak_queue_depth: checkpoint.peak_queue_depth,
+            stalled: checkpoint.stalled,
+            arrival_seed: checkpoint.arrival_seed,
+            service_seed: checkpoint.service_seed,
+            drawn_seeds: checkpoint.drawn_seeds,
+            rng: Box::new(SmallRng::seed_from_u64(checkpoint.rng_seed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::routing::Random;
+    use crate::Server;
+    use rand::rngs::mock::StepRng;
+
+    const TICK_SIZE: Duration = Duration::new(0, 50_000_000);
+    const ONE_HOUR: Duration = Duration::new(60 * 60, 0);
+
+    fn simulation(routing_strategy: Box<dyn RoutingStrategy>) -> Result<Simulation> {
+        let step = u64::MAX / 3600;
+        let mut sim = Simulation::new(ONE_HOUR, TICK_SIZE, Box::new(StepRng::new(1, step)));
+
+        let client = Client {
+            transfer_rate: 0.5,
+            ..Client::default()
+        };
+        sim.add_clients(vec![client; 40])?;
+        sim.add_servers(vec![Server::default(); 3])?;
+        sim.set_routing_strategy(routing_strategy)?;
+        sim.set_record_routes(true)?;
+        sim.set_record_occupancy(true)?;
+        sim.enable()?;
+
+        Ok(sim)
+    }
+
+    fn tick_until(sim: &mut Simulation, tick: Duration) {
+        while sim.running().1 < tick && sim.tick() {}
+    }
+
+    /// Sorted wait times, the busy time of each server, and the tick of each route. Ids differ
+    /// between simulations, and for requests spawned by a transfer after a checkpoint, so they are
+    /// omitted.
+    type Outcome = (Vec<Option<Duration>>, Vec<Duration>, Vec<Duration>);
+
+    fn outcome(sim: &Simulation) -> Outcome {
+        let mut waits: Vec<_> = sim.request_data().iter().map(|d| d.wait_time).collect();
+        waits.sort_unstable();
+        let busy = sim.server_stats().iter().map(|s| s.busy).collect();
+        let routes = sim.routes().iter().map(|(tick, _, _)| *tick).collect();
+        (waits, busy, routes)
+    }
+
+    #[test]
+    fn requires_a_running_simulation() {
+        let mut sim = Simulation::new(ONE_HOUR, TICK_SIZE, Box::new(StepRng::new(1, 1)));
+
+        assert!(matches!(sim.checkpoint(), Err(Error::NotRunning)));
+    }
+
+    #[test]
+    fn continues_like_the_checkpointed_simulation() -> Result<()> {
+        let mut sim = simulation(Box::new(Random))?;
+        tick_until(&mut sim, ONE_HOUR / 2);
+
+        let checkpoint = sim.checkpoint()?;
+        let mut restored = Simulation::restore(checkpoint, Box::new(Random));
+        assert_eq!(sim.running(), restored.running());
+
+        while sim.tick() {}
+        while restored.tick() {}
+
+        assert_eq!(sim.totals(), restored.totals());
+        assert_eq!(sim.occupancy_series(), restored.occupancy_series());
+        Ok(assert_eq!(outcome(&sim), outcome(&restored)))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resumes_from_a_serialized_checkpoint() -> Result<()> {
+        use crate::routing::Greedy;
+
+        let mut straight = simulation(Box::new(Greedy))?;
+        while straight.tick() {}
+
+        let mut sim = simulation(Box::new(Greedy))?;
+        tick_until(&mut sim, ONE_HOUR / 2);
+        let (_, tick) = sim.running();
+        let serialized = toml::to_string(&sim.checkpoint()?).expect("Checkpoint serializes");
+        drop(sim);
+
+        let checkpoint: Checkpoint = toml::from_str(&serialized).expect("Checkpoint deserializes");
+        assert_eq!(tick, checkpoint.tick());
+        let mut restored = Simulation::restore(checkpoint, Box::new(Greedy));
+        while restored.tick() {}
+
+        assert_eq!(straight.totals(), restored.totals());
+        assert_eq!(straight.occupancy_series(), restored.occupancy_series());
+        Ok(assert_eq!(outcome(&straight), outcome(&restored)))
+    }
+}
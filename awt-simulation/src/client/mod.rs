@@ -1,26 +1,58 @@
 use alloc::vec::Vec;
 use core::time::Duration;
 
-use crate::Attribute;
+use crate::arrival::{ArrivalPattern, Batch};
+use crate::{distribution::Distribution, Attribute, ContactType};
 
 const FIVE_MINUTES: Duration = Duration::new(300, 0);
 const THIRTY_SECONDS: Duration = Duration::new(30, 0);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Client {
+    pub channel: ContactType,
     pub required_attributes: Vec<Attribute>,
     pub handle_time: Duration,
     pub clean_up_time: Duration,
     pub abandon_time: Duration,
+    /// The distribution each request's abandon time is sampled from, using `abandon_time` as the
+    /// scale.
+    pub abandon_distribution: Distribution,
+    /// The number of `Server`s which must simultaneously handle each request, e.g. 2 for a
+    /// supervisor join. The request is handled at the pace of the slowest of them, which it is
+    /// served by.
+    pub required_servers: usize,
+    /// How the start of each request is spread across the simulation.
+    pub arrival_pattern: ArrivalPattern,
+    /// The fraction of requests (`0.0..=1.0`) which opt into a callback when they reach their
+    /// abandon time, waiting to be called back rather than abandoning.
+    pub callback_rate: f64,
+    /// The fraction of requests (`0.0..=1.0`) which are transferred when answered, each spawning
+    /// a follow-up request which arrives once handling ends.
+    pub transfer_rate: f64,
+    /// The fraction of requests (`0.0..=1.0`) which hang up before being enqueued, e.g. in an IVR
+    /// menu. They are abandoned when they arrive, without waiting.
+    pub pre_queue_drop_rate: f64,
+    /// Delivers requests in batches with those of other clients with the same `Batch`, rather
+    /// than individually.
+    pub batch: Option<Batch>,
 }
 
 impl Default for Client {
     fn default() -> Self {
         Self {
+            channel: ContactType::default(),
             required_attributes: Vec::new(),
             handle_time: FIVE_MINUTES,
             clean_up_time: Duration::ZERO,
             abandon_time: THIRTY_SECONDS,
+            abandon_distribution: Distribution::default(),
+            required_servers: 1,
+            arrival_pattern: ArrivalPattern::default(),
+            callback_rate: 0.0,
+            transfer_rate: 0.0,
+            pre_queue_drop_rate: 0.0,
+            batch: None,
         }
     }
 }
@@ -3,15 +3,18 @@ use core::time::Duration;
 use rand::RngCore;
 
 use crate::client::Client;
+use crate::request::Data;
+use crate::routing::RoutingStrategy;
 use crate::server::Server;
-use crate::Simulation;
+use crate::{Result, Simulation};
 
 pub struct Config {
     pub(crate) end: Duration,
     pub(crate) tick_size: Duration,
     pub(crate) clients: Vec<Client>,
     pub(crate) servers: Vec<Server>,
-    pub(crate) rng: Box<dyn RngCore>,
+    pub(crate) rng: Box<dyn RngCore + Send>,
+    pub(crate) routing_strategy: Option<Box<dyn RoutingStrategy + Send>>,
 }
 
 impl alloc::fmt::Debug for Config {
@@ -28,13 +31,14 @@ impl alloc::fmt::Debug for Config {
 // Constructors
 impl Config {
     #[must_use]
-    pub fn new(end: Duration, tick_size: Duration, rng: Box<dyn RngCore>) -> Self {
+    pub fn new(end: Duration, tick_size: Duration, rng: Box<dyn RngCore + Send>) -> Self {
         Self {
             end,
             tick_size,
             clients: Vec::new(),
             servers: Vec::new(),
             rng,
+            routing_strategy: None,
         }
     }
 }
@@ -55,6 +59,11 @@ impl Config {
     pub fn set_servers(&mut self, servers: Vec<Server>) {
         self.servers = servers;
     }
+
+    /// Sets the `RoutingStrategy` of the `Simulation`. Default is `Greedy`.
+    pub fn set_routing_strategy(&mut self, strategy: Box<dyn RoutingStrategy + Send>) {
+        self.routing_strategy = Some(strategy);
+    }
 }
 
 impl From<Config> for Simulation {
@@ -62,10 +71,44 @@ impl From<Config> for Simulation {
     ///
     /// This function consumes the provided config so ensure the config is cloned before trying to
     /// use in other simulations.
-    fn from(mut config: Config) -> Self {
+    fn from(config: Config) -> Self {
         let mut sim = Self::new(config.end, config.tick_size, config.rng);
-        sim.add_servers(config.servers);
-        sim.add_clients(&mut config.clients);
+        sim.add_servers(config.servers)
+            .expect("A new Simulation is not enabled");
+        sim.add_clients(config.clients)
+            .expect("A new Simulation is not enabled");
+        if let Some(strategy) = config.routing_strategy {
+            sim.set_routing_strategy(strategy)
+                .expect("A new Simulation is not enabled");
+        }
         sim
     }
 }
+
+/// Runs a `Simulation` from `config` to completion, returning the `Data` of every request.
+///
+/// ```
+/// use awt_simulation::{client::Client, request::Status, server::Server, Config};
+/// use core::time::Duration;
+/// use rand::{rngs::SmallRng, SeedableRng};
+///
+/// let rng = Box::new(SmallRng::seed_from_u64(1));
+/// let mut config = Config::new(Duration::from_secs(3600), Duration::from_secs(1), rng);
+/// config.set_clients(vec![Client::default(); 10]);
+/// config.add_server(Server::default());
+///
+/// let data = awt_simulation::run(config).unwrap();
+/// assert_eq!(10, data.len());
+/// assert!(data.iter().all(|d| d.status != Status::Pending));
+/// ```
+///
+/// # Errors
+///
+/// Will error if the `Simulation` can't be enabled, or it stalls whilst ticking.
+pub fn run(config: Config) -> Result<Vec<Data>> {
+    let mut sim = Simulation::from(config);
+    sim.enable()?;
+    while sim.try_tick()? {}
+
+    Ok(sim.request_data())
+}
@@ -0,0 +1,15 @@
+/// The channel a `Request` is made through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContactType {
+    #[default]
+    Call,
+    Email,
+    Chat,
+    SocialMedia,
+}
+
+impl ContactType {
+    /// All of the available `ContactType`s
+    pub const ALL: [ContactType; 4] = [Self::Call, Self::Email, Self::Chat, Self::SocialMedia];
+}
@@ -0,0 +1,79 @@
+use core::time::Duration;
+use rand::Rng;
+
+/// A distribution used to sample `Duration`s around a configured scale.
+///
+/// Distributions other than `Fixed` require the `std` feature for floating point math.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Distribution {
+    /// Always returns the scale.
+    #[default]
+    Fixed,
+    /// Exponentially distributed with a mean of the scale.
+    #[cfg(feature = "std")]
+    Exponential,
+    /// Weibull distributed with the provided shape, which must be positive and finite. The scale is
+    /// the Weibull scale parameter.
+    #[cfg(feature = "std")]
+    Weibull(f64),
+}
+
+impl Distribution {
+    /// Sample a `Duration` from this distribution with the provided `scale`. Samples are drawn by
+    /// inverse transform sampling.
+    ///
+    /// `Fixed` does not draw from the `rng`.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub fn sample<R: Rng + ?Sized>(&self, scale: Duration, rng: &mut R) -> Duration {
+        match self {
+            Self::Fixed => scale,
+            #[cfg(feature = "std")]
+            Self::Exponential => scale.mul_f64(-(1.0 - rng.gen::<f64>()).ln()),
+            #[cfg(feature = "std")]
+            Self::Weibull(shape) => {
+                scale.mul_f64((-(1.0 - rng.gen::<f64>()).ln()).powf(1.0 / shape))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    const SCALE: Duration = Duration::new(60, 0);
+    #[cfg(feature = "std")]
+    const SAMPLES: u32 = 10_000;
+
+    #[cfg(feature = "std")]
+    fn mean(distribution: Distribution) -> f64 {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let sum: Duration = (0..SAMPLES)
+            .map(|_| distribution.sample(SCALE, &mut rng))
+            .sum();
+        (sum / SAMPLES).as_secs_f64()
+    }
+
+    #[test]
+    fn fixed_is_scale() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        assert_eq!(SCALE, Distribution::Fixed.sample(SCALE, &mut rng));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn exponential_mean_is_scale() {
+        let mean = mean(Distribution::Exponential);
+        assert!((mean - 60.0).abs() < 3.0, "mean was {mean}");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn weibull_mean() {
+        // Mean of a Weibull is scale * gamma(1 + 1/shape), gamma(1.5) = 0.886_226_9
+        let mean = mean(Distribution::Weibull(2.0));
+        assert!((mean - 60.0 * 0.886_226_9).abs() < 3.0, "mean was {mean}");
+    }
+}
@@ -0,0 +1,15 @@
+use core::time::Duration;
+
+/// When a `Simulation` stops ticking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EndCondition {
+    /// Stops at the tick, which is also the end requests arrive by. The default, at the `end` the
+    /// `Simulation` was created with.
+    AtTick(Duration),
+    /// Stops once at least this many requests have been answered or abandoned, or at the `end`
+    /// the `Simulation` was created with if that comes first. Stopping early makes the tick it
+    /// stopped at the end, so statistics only cover the time simulated. Several requests can
+    /// resolve on the same tick, so slightly more may be resolved.
+    AfterRequests(usize),
+}
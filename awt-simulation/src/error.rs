@@ -1,15 +1,92 @@
-use core::{fmt, fmt::Display, fmt::Formatter};
+use core::{fmt, fmt::Display, fmt::Formatter, time::Duration};
 
 #[derive(Debug)]
 pub enum Error {
-    Enabled,
+    /// The `Operation` can only be performed before the `Simulation` is enabled.
+    Enabled(Operation),
+    ZeroTickSize,
+    NotRunning,
+    UnknownServer(usize),
+    /// The `Simulation` stopped advancing at this tick.
+    Stalled(Duration),
+}
+
+/// An error from the request or server queue, e.g. when routing to an id which is stale.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueError {
+    /// The request or server with this id is not waiting to be routed.
+    NotWaiting(usize),
+}
+
+impl Display for QueueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotWaiting(id) => write!(f, "{id} is not waiting to be routed"),
+        }
+    }
+}
+
+/// A `Simulation` method which can only be called before the `Simulation` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    AddServer,
+    AddServers,
+    AddClient,
+    AddClients,
+    SetRoutingStrategy,
+    SetTickMode,
+    SetEndCondition,
+    SetRecordOccupancy,
+    SetRecordRoutes,
+    SetRecordQueueDepth,
+    SetArrivalSeed,
+    SetServiceSeed,
+    Generate,
+    Enable,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let method = match self {
+            Self::AddServer => "add_server",
+            Self::AddServers => "add_servers",
+            Self::AddClient => "add_client",
+            Self::AddClients => "add_clients",
+            Self::SetRoutingStrategy => "set_routing_strategy",
+            Self::SetTickMode => "set_tick_mode",
+            Self::SetEndCondition => "set_end_condition",
+            Self::SetRecordOccupancy => "set_record_occupancy",
+            Self::SetRecordRoutes => "set_record_routes",
+            Self::SetRecordQueueDepth => "set_record_queue_depth",
+            Self::SetArrivalSeed => "set_arrival_seed",
+            Self::SetServiceSeed => "set_service_seed",
+            Self::Generate => "generate",
+            Self::Enable => "enable",
+        };
+        write!(f, "{method}")
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "Cannot modify an enabled simulation")
+        match self {
+            Self::Enabled(operation) => {
+                write!(
+                    f,
+                    "Cannot {operation} when the simulation is already enabled"
+                )
+            }
+            Self::ZeroTickSize => write!(f, "Simulation tick size must be greater than zero"),
+            Self::NotRunning => write!(f, "Simulation must be enabled and not yet complete"),
+            Self::UnknownServer(id) => write!(f, "Server {id} is not in the simulation"),
+            Self::Stalled(tick) => write!(f, "Simulation stalled at {tick:?} without advancing"),
+        }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueueError {}
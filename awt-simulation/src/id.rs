@@ -0,0 +1,49 @@
+//! Counters which give each `Request` and `Server` a unique id.
+//!
+//! Ids are generated from a global atomic counter by default. With the `local-ids` feature, ids
+//! come from a thread local counter instead, which avoids atomic operations whilst generating
+//! requests. Ids are then only unique amongst those generated on the same thread, so every
+//! `Server` in a `Simulation` must be created on the same thread.
+
+/// Defines `fn next_id() -> usize` in the calling module, backed by its own counter, along with
+/// `fn skip_ids_past(id: usize)` so that ids restored from a `Checkpoint` are not generated again.
+macro_rules! id_counter {
+    () => {
+        #[cfg(not(feature = "local-ids"))]
+        static ID_COUNTER: core::sync::atomic::AtomicUsize =
+            core::sync::atomic::AtomicUsize::new(0);
+
+        #[cfg(feature = "local-ids")]
+        std::thread_local! {
+            static ID_COUNTER: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+        }
+
+        #[cfg(not(feature = "local-ids"))]
+        fn next_id() -> usize {
+            ID_COUNTER.fetch_add(1, core::sync::atomic::Ordering::SeqCst)
+        }
+
+        #[cfg(feature = "local-ids")]
+        fn next_id() -> usize {
+            ID_COUNTER.with(|counter| {
+                let id = counter.get();
+                counter.set(id + 1);
+                id
+            })
+        }
+
+        /// Ensures every id generated from now on is greater than `id`.
+        #[cfg(not(feature = "local-ids"))]
+        pub(crate) fn skip_ids_past(id: usize) {
+            ID_COUNTER.fetch_max(id + 1, core::sync::atomic::Ordering::SeqCst);
+        }
+
+        /// Ensures every id generated from now on is greater than `id`.
+        #[cfg(feature = "local-ids")]
+        pub(crate) fn skip_ids_past(id: usize) {
+            ID_COUNTER.with(|counter| counter.set(counter.get().max(id + 1)));
+        }
+    };
+}
+
+pub(crate) use id_counter;
@@ -17,55 +17,104 @@ extern crate std;
 
 extern crate alloc;
 
+pub mod arrival;
 pub mod attribute;
+pub mod checkpoint;
 pub mod client;
+pub mod contact_type;
+pub mod distribution;
+pub mod end_condition;
 pub mod error;
 pub mod request;
+pub mod routing;
 pub mod server;
+pub mod tick_mode;
+pub mod ticks;
 
+mod builder;
 mod config;
-mod routing;
+mod id;
 
 use core::time::Duration;
-use rand::{Rng, RngCore};
+use rand::{rngs::SmallRng, Rng, RngCore, SeedableRng};
 
 use alloc::{boxed::Box, vec::Vec};
 use attribute::Attribute;
 use client::Client;
-use error::Error;
-use request::{queue::Queue as RequestQueue, Data as RequestData, Request};
-use routing::route_requests;
+use contact_type::ContactType;
+use end_condition::EndCondition;
+use error::{Error, Operation};
+use request::{queue::Queue as RequestQueue, Request};
+use routing::{group_routes, Greedy, RoutingStrategy};
 use server::{queue::Queue as ServerQueue, QueueableServer, Server};
+use tick_mode::TickMode;
+use ticks::Ticks;
 
-pub use config::Config;
+pub use builder::SimulationBuilder;
+pub use config::{run, Config};
+pub use routing::{RequestData, ServerData};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// The number of consecutive ticks which can fail to advance before a `Simulation` has stalled.
+const STALL_LIMIT: usize = 1_000;
+
+#[allow(clippy::struct_excessive_bools)]
 pub struct Simulation {
     start: Duration,
     tick: Duration,
     tick_size: Duration,
+    tick_mode: TickMode,
     end: Duration,
+    end_condition: EndCondition,
     running: bool,
     clients: Vec<Client>,
     request_queue: RequestQueue,
     server_queue: ServerQueue,
-    rng: Box<dyn RngCore>,
+    routing_strategy: Box<dyn RoutingStrategy>,
+    record_occupancy: bool,
+    occupancy: Vec<(Duration, usize)>,
+    record_routes: bool,
+    routes: Vec<(Duration, usize, usize)>,
+    record_queue_depth: bool,
+    queue_depth: Vec<(Duration, usize)>,
+    peak_queue_depth: usize,
+    // Consecutive ticks which did not advance
+    stalled: usize,
+    arrival_seed: Option<u64>,
+    service_seed: Option<u64>,
+    // The arrival and service seeds drawn from `rng`, kept so regenerating draws the same seeds
+    drawn_seeds: Option<(u64, u64)>,
+    rng: Box<dyn RngCore + Send>,
 }
 
 impl Simulation {
     /// Generate a new Simulation
     #[must_use]
-    pub fn new(end: Duration, tick_size: Duration, rng: Box<dyn RngCore>) -> Self {
+    pub fn new(end: Duration, tick_size: Duration, rng: Box<dyn RngCore + Send>) -> Self {
         Self {
             start: Duration::ZERO,
             tick: Duration::ZERO,
             tick_size,
+            tick_mode: TickMode::default(),
             end,
+            end_condition: EndCondition::AtTick(end),
             running: false,
             clients: Vec::new(),
             request_queue: RequestQueue::default(),
             server_queue: ServerQueue::default(),
+            routing_strategy: Box::new(Greedy),
+            record_occupancy: false,
+            occupancy: Vec::new(),
+            record_routes: false,
+            routes: Vec::new(),
+            record_queue_depth: false,
+            queue_depth: Vec::new(),
+            peak_queue_depth: 0,
+            stalled: 0,
+            arrival_seed: None,
+            service_seed: None,
+            drawn_seeds: None,
             rng,
         }
     }
@@ -80,16 +129,59 @@ impl Simulation {
     /// Will error when `Simulation` is already enabled.
     pub fn add_server(&mut self, server: Server) -> Result<()> {
         if self.running {
-            return Err(Error::Enabled);
+            return Err(Error::Enabled(Operation::AddServer));
         }
         self.server_queue.push(QueueableServer::new(server));
         Ok(())
     }
 
-    fn add_servers(&mut self, servers: Vec<Server>) {
+    /// Add many `Server`s to the `Simulation`
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn add_servers(&mut self, servers: Vec<Server>) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::AddServers));
+        }
+
         for server in servers {
             self.server_queue.push(QueueableServer::new(server));
         }
+
+        Ok(())
+    }
+
+    /// Add a `Server` to a running `Simulation`, e.g. to staff a growing queue. The `Server` is
+    /// idle, and can be routed to from the current tick. Use `add_server` before the `Simulation`
+    /// is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Will error when the `Simulation` is not running.
+    pub fn add_server_live(&mut self, server: Server) -> Result<()> {
+        if !self.running {
+            return Err(Error::NotRunning);
+        }
+
+        self.server_queue
+            .push_live(QueueableServer::new(server), self.tick);
+        Ok(())
+    }
+
+    /// Remove the `Server` with `id`, e.g. when it leaves for a break. Can be called while the
+    /// `Simulation` is running. An idle `Server` is removed immediately, whilst a busy `Server`
+    /// finishes its current request first.
+    ///
+    /// # Errors
+    ///
+    /// Will error when there is no `Server` with `id`, or it was already removed.
+    pub fn remove_server(&mut self, id: usize) -> Result<()> {
+        if self.server_queue.remove(id, self.tick) {
+            Ok(())
+        } else {
+            Err(Error::UnknownServer(id))
+        }
     }
 
     /// Add a `Client` to the `Simulation`
@@ -99,31 +191,200 @@ impl Simulation {
     /// Will error when `Simulation` is already enabled.
     pub fn add_client(&mut self, client: Client) -> Result<()> {
         if self.running {
-            return Err(Error::Enabled);
+            return Err(Error::Enabled(Operation::AddClient));
         }
 
         self.clients.push(client);
+        self.discard_generated();
+
+        Ok(())
+    }
+
+    /// Add many `Client`s to the `Simulation`
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn add_clients(&mut self, mut clients: Vec<Client>) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::AddClients));
+        }
+
+        self.clients.append(&mut clients);
+        self.discard_generated();
+
+        Ok(())
+    }
+
+    /// Set the `RoutingStrategy` used to assign `Request`s to `Server`s. Default is `Greedy`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_routing_strategy(&mut self, strategy: Box<dyn RoutingStrategy>) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetRoutingStrategy));
+        }
+
+        self.routing_strategy = strategy;
+
+        Ok(())
+    }
+
+    /// Set how the `Simulation` advances between ticks. Default is `TickMode::EventDriven`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_tick_mode(&mut self, tick_mode: TickMode) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetTickMode));
+        }
+
+        self.tick_mode = tick_mode;
+
+        Ok(())
+    }
+
+    /// Set when the `Simulation` stops. Default is `EndCondition::AtTick` the `end` it was created
+    /// with. `AtTick` replaces the `end`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_end_condition(&mut self, end_condition: EndCondition) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetEndCondition));
+        }
+
+        if let EndCondition::AtTick(end) = end_condition {
+            self.end = end;
+        }
+        self.end_condition = end_condition;
+
+        Ok(())
+    }
+
+    /// Record a `(tick, busy_servers)` sample on every tick, available from `occupancy_series()`.
+    /// Default is off.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_record_occupancy(&mut self, record: bool) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetRecordOccupancy));
+        }
+
+        self.record_occupancy = record;
+
+        Ok(())
+    }
+
+    /// Record a `(tick, request_id, server_id)` entry for every route which is handled, available
+    /// from `routes()`. Default is off.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_record_routes(&mut self, record: bool) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetRecordRoutes));
+        }
+
+        self.record_routes = record;
+
+        Ok(())
+    }
+
+    /// Record a `(tick, waiting_requests)` sample on every tick, available from
+    /// `queue_depth_series()`. The peak is tracked either way, see `peak_queue_depth()`. Default
+    /// is off.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_record_queue_depth(&mut self, record: bool) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetRecordQueueDepth));
+        }
+
+        self.record_queue_depth = record;
+
+        Ok(())
+    }
+
+    /// Seed the stream which each request's arrival is sampled from. Default is derived from the
+    /// `Simulation`'s rng. Holding this constant whilst changing the service seed varies only the
+    /// abandon times of the same arrivals.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_arrival_seed(&mut self, seed: u64) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetArrivalSeed));
+        }
+
+        self.arrival_seed = Some(seed);
+        self.discard_generated();
+
+        Ok(())
+    }
+
+    /// Seed the stream which each request's abandon time is sampled from. Default is derived from
+    /// the `Simulation`'s rng.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn set_service_seed(&mut self, seed: u64) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::SetServiceSeed));
+        }
+
+        self.service_seed = Some(seed);
+        self.discard_generated();
 
         Ok(())
     }
 
-    fn add_clients(&mut self, clients: &mut Vec<Client>) {
-        self.clients.append(clients);
+    /// Generates the requests of every `Client`, so they can be inspected with `pending_requests()`
+    /// before the `Simulation` is enabled. Does nothing if the requests are already generated.
+    /// Adding clients or setting a seed afterwards discards the requests, which are generated again
+    /// the same as if the change was made first.
+    ///
+    /// # Errors
+    ///
+    /// Will error when `Simulation` is already enabled.
+    pub fn generate(&mut self) -> Result<()> {
+        if self.running {
+            return Err(Error::Enabled(Operation::Generate));
+        }
+
+        // Requests are only generated when there are none, i.e. when none were generated since they
+        // were last discarded
+        if self.request_queue.requests().is_empty() {
+            self.generate_requests();
+        }
+
+        Ok(())
     }
 
     /// Enables the `Simulation` which will generate and schedule all simulation elements. The `Simulation` can then be
-    /// advanced by calling the `tick()` until it returns false.
+    /// advanced by calling the `tick()` until it returns false. Requests are only generated if
+    /// `generate()` wasn't already called.
     ///
     /// # Errors
     ///
     /// Will error if already enabled.
     pub fn enable(&mut self) -> Result<bool> {
         if self.running {
-            return Err(Error::Enabled);
+            return Err(Error::Enabled(Operation::Enable));
         }
 
+        self.generate()?;
         self.running = true;
-        self.generate_requests();
 
         self.server_queue.init();
         self.request_queue.init();
@@ -137,91 +398,367 @@ impl Simulation {
         (self.running, self.tick)
     }
 
+    /// The tick the `Simulation` starts at.
+    #[must_use]
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// The tick the `Simulation` ends at. An `EndCondition::AfterRequests` ends earlier, and this
+    /// is then the tick it ended.
+    #[must_use]
+    pub fn end(&self) -> Duration {
+        self.end
+    }
+
+    /// The duration of each tick.
+    #[must_use]
+    pub fn tick_size(&self) -> Duration {
+        self.tick_size
+    }
+
+    /// The `Data` of every request. Handling which is still in progress at the end of the
+    /// `Simulation` is only counted up until the end.
+    #[must_use]
+    pub fn request_data(&self) -> Vec<request::Data> {
+        self.request_queue
+            .requests()
+            .iter()
+            .map(|request| request.borrow().data_until(self.end))
+            .collect()
+    }
+
+    /// The `Data` of each request which has not yet arrived, in the order they arrive. Before the
+    /// `Simulation` is enabled, this is every request generated with `generate()`.
+    #[must_use]
+    pub fn pending_requests(&self) -> Vec<request::Data> {
+        let mut pending: Vec<request::Data> = self
+            .request_queue
+            .requests()
+            .iter()
+            .map(|request| request.borrow())
+            .filter(|request| &request::Status::Pending == request.status())
+            .map(|request| request.data_until(self.end))
+            .collect();
+        pending.sort_unstable_by_key(|data| (data.start, data.id));
+        pending
+    }
+
+    /// Takes the `Data` of each request which was answered or abandoned since this was last
+    /// called, in the order they completed. Call after each `tick()`, including the final tick, to
+    /// report requests as the `Simulation` runs rather than all at once with `request_data()`.
+    pub fn take_completed(&mut self) -> Vec<request::Data> {
+        self.request_queue
+            .take_completed()
+            .iter()
+            .map(|request| request.borrow().data_until(self.end))
+            .collect()
+    }
+
+    /// The number of requests offered, answered, abandoned, and still pending. Cheaper than
+    /// collecting `request_data()`, and doesn't require any metrics.
     #[must_use]
-    pub fn request_data(&self) -> Vec<RequestData> {
+    pub fn totals(&self) -> request::RunTotals {
         self.request_queue
             .requests()
             .iter()
-            .map(|request| request.borrow().data())
+            .map(|request| *request.borrow().status())
             .collect()
     }
+
+    /// The number of busy servers after routing on each tick, in tick order. Only populated when
+    /// enabled with `set_record_occupancy()`.
+    #[must_use]
+    pub fn occupancy_series(&self) -> &[(Duration, usize)] {
+        &self.occupancy
+    }
+
+    /// The `(tick, request_id, server_id)` of each handled route, in the order they were handled.
+    /// Only populated when enabled with `set_record_routes()`.
+    #[must_use]
+    pub fn routes(&self) -> &[(Duration, usize, usize)] {
+        &self.routes
+    }
+
+    /// The busy and available time of each server during the `Simulation`.
+    #[must_use]
+    pub fn server_stats(&self) -> Vec<server::Stats> {
+        self.server_queue.stats(self.start, self.end)
+    }
+
+    /// The `(server id, count)` of requests each server handled, in the order the servers were
+    /// added. A request handled by multiple servers is counted for each of them.
+    #[must_use]
+    pub fn server_call_counts(&self) -> Vec<(usize, usize)> {
+        self.server_queue.call_counts()
+    }
+
+    /// The number of waiting requests on each tick, in tick order. Only populated when enabled
+    /// with `set_record_queue_depth()`.
+    #[must_use]
+    pub fn queue_depth_series(&self) -> &[(Duration, usize)] {
+        &self.queue_depth
+    }
+
+    /// The largest number of requests waiting at once, whether or not the series is recorded.
+    #[must_use]
+    pub fn peak_queue_depth(&self) -> usize {
+        self.peak_queue_depth
+    }
+
+    /// An estimate of the server time needed for the work at the current tick: the handle time of
+    /// each waiting request, for each server it requires, plus the time until each busy server is
+    /// released. e.g. to decide when to add servers with `add_server_live()`. Server efficiency is
+    /// not known until a request is routed, so waiting requests are estimated without it.
+    #[must_use]
+    pub fn workload_estimate(&self) -> Duration {
+        self.request_queue.waiting_work() + self.server_queue.remaining_work(self.tick)
+    }
 }
 
 // Generators and state modifiers
 impl Simulation {
+    /// Each client samples from its own rng, seeded from the `Simulation`s rng and the client's
+    /// index. Adding or removing a client doesn't change the requests of any earlier client.
     fn generate_requests(&mut self) {
-        let mut request_from_client = |c: &Client| -> Request {
-            let start = self.rng.gen_range(self.start..=self.end);
-            let abandon_ticks = start + c.abandon_time;
+        // Both seeds are always drawn, so setting one doesn't change the other
+        let rng = &mut self.rng;
+        let (arrival_seed, service_seed) = *self
+            .drawn_seeds
+            .get_or_insert_with(|| (rng.gen(), rng.gen()));
+        let arrival_seed = self.arrival_seed.unwrap_or(arrival_seed);
+        let service_seed = self.service_seed.unwrap_or(service_seed);
+
+        let mut starts: Vec<Duration> = self
+            .clients
+            .iter()
+            .enumerate()
+            .map(|(index, c)| {
+                let mut arrival_rng =
+                    SmallRng::seed_from_u64(arrival_seed.wrapping_add(index as u64));
+                c.arrival_pattern
+                    .sample(self.start, self.end, &mut arrival_rng)
+            })
+            .collect();
+        arrival::batch_arrivals(&self.clients, &mut starts, self.start, self.end);
+
+        let request_from_client = |index: usize, c: &Client| -> Request {
+            let start = starts[index];
+            let mut service_rng = SmallRng::seed_from_u64(service_seed.wrapping_add(index as u64));
+
+            let abandon_ticks = start
+                + c.abandon_distribution
+                    .sample(c.abandon_time, &mut service_rng);
             let handle_ticks = c.handle_time;
 
-            Request::new(
+            let mut request = Request::new(
                 start,
                 abandon_ticks,
                 handle_ticks,
                 c.required_attributes.clone(),
                 c,
-            )
+            );
+            if c.callback_rate > 0.0 && service_rng.gen_bool(c.callback_rate.min(1.0)) {
+                request.opt_into_callback();
+            }
+            if c.transfer_rate > 0.0 && service_rng.gen_bool(c.transfer_rate.min(1.0)) {
+                request.opt_into_transfer();
+            }
+            if c.pre_queue_drop_rate > 0.0 && service_rng.gen_bool(c.pre_queue_drop_rate.min(1.0)) {
+                request.abandon_before_queue();
+            }
+            request
         };
 
-        for client in &self.clients {
-            self.request_queue.push(request_from_client(client));
+        self.request_queue.reserve(self.clients.len());
+        for (index, client) in self.clients.iter().enumerate() {
+            self.request_queue.push(request_from_client(index, client));
+        }
+    }
+
+    /// Discards any generated requests, so they are generated again with the current clients and
+    /// seeds.
+    fn discard_generated(&mut self) {
+        if !self.request_queue.requests().is_empty() {
+            self.request_queue = RequestQueue::default();
         }
     }
 }
 
 // Simulation logic
 impl Simulation {
+    /// Returns an iterator which advances the `Simulation`, yielding the tick after each advance
+    /// until the `Simulation` completes. An alternative to calling `tick()` until it returns false.
+    ///
+    /// # Errors
+    ///
+    /// Will error if the `Simulation` is not running, i.e. not enabled or already complete.
+    pub fn ticks(&mut self) -> Result<Ticks<'_>> {
+        if !self.running {
+            return Err(Error::NotRunning);
+        }
+
+        Ok(Ticks::new(self))
+    }
+
+    /// Advances the `Simulation` by a single tick. Returns whether it is still running.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `Simulation` has stalled, see `try_tick()`.
     pub fn tick(&mut self) -> bool {
+        match self.try_tick() {
+            Ok(running) => running,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Advances the `Simulation` by a single tick. Returns whether it is still running.
+    ///
+    /// # Errors
+    ///
+    /// Will error if the tick has not advanced for many consecutive ticks, e.g. a zero tick size
+    /// whilst requests are waiting, rather than ticking forever.
+    pub fn try_tick(&mut self) -> Result<bool> {
         if !self.running {
-            return false;
+            return Ok(false);
         }
+        if self.stalled >= STALL_LIMIT {
+            return Err(Error::Stalled(self.tick));
+        }
+        let previous = self.tick;
 
         // release requests and servers from queues
         self.request_queue.tick(self.tick);
+        let waiting = self.request_queue.waiting_count();
+        self.peak_queue_depth = self.peak_queue_depth.max(waiting);
+        if self.record_queue_depth {
+            self.queue_depth.push((self.tick, waiting));
+        }
         self.server_queue.tick(self.tick);
 
         // assign the relevant servers
         self.do_routing();
 
+        if self.record_occupancy {
+            self.occupancy
+                .push((self.tick, self.server_queue.busy_count()));
+        }
+
         // tick the main simulation
         self.increment_tick();
 
-        self.running
+        if self.running && self.tick == previous {
+            self.stalled += 1;
+            if self.stalled >= STALL_LIMIT {
+                return Err(Error::Stalled(self.tick));
+            }
+        } else {
+            self.stalled = 0;
+        }
+
+        Ok(self.running)
     }
 
     /// Routing is fairly straight forward to orchestrate.
     ///
-    /// 1. Each request in the queue (gauranteed to be in order of ticks) is passed to the router
-    ///    along with a list of available servers.
-    /// 2. If the router returns a server, then that server is assigned to the request
+    /// 1. Each request in the queue (gauranteed to be in order of ticks) is passed to the
+    ///    `RoutingStrategy` along with a list of available servers.
+    /// 2. If the strategy returns a server, then that server is assigned to the request
     /// 3. The server is pulled into a buffer for the expected number of ticks, and removed from
-    ///    the pool
+    ///    the pool. Requests which require multiple servers are only assigned when all of them
+    ///    are routed, and each server is held for the same number of ticks
     fn do_routing(&mut self) {
-        let request_data = self.request_queue.routing_data();
-        let server_data = self.server_queue.routing_data();
-
         // Only attempt to route if there is actually something that could be done
-        if request_data.is_empty() || server_data.is_empty() {
+        let request_data = self.request_queue.routing_data();
+        if request_data.is_empty() {
+            return;
+        }
+        let server_data = self.server_queue.routing_data(self.rng.as_mut());
+        if server_data.is_empty() {
             return;
         }
 
-        for (request_id, server_id) in route_requests(request_data, server_data) {
-            // TODO: It's possible that the request_id or server_id are not available in the queue,
-            // which could lead to panics. This should "fail safely".
-            let release_tick = self.request_queue.handle_request(request_id, self.tick);
+        let routes =
+            self.routing_strategy
+                .route(self.tick, request_data, server_data, self.rng.as_mut());
+
+        // Tags for requests which are no longer waiting are ignored, like stale routes
+        for (request_id, tag) in self.routing_strategy.take_tags() {
+            let _ = self.request_queue.tag_request(request_id, tag);
+        }
+
+        for (request_id, server_ids) in group_routes(routes) {
+            // Requests are only handled once all of their required servers are routed
+            if self.request_queue.required_servers(request_id) != Some(server_ids.len()) {
+                continue;
+            }
+
+            // A strategy may return stale ids, which are skipped. Every server is checked before
+            // the request is handled, so a request is never handled without all of its servers.
+            let mut unique = server_ids.clone();
+            unique.sort_unstable();
+            unique.dedup();
+            if unique.len() != server_ids.len()
+                || !server_ids
+                    .iter()
+                    .all(|id| self.server_queue.waiting_server(*id).is_some())
+            {
+                continue;
+            }
 
-            self.server_queue.enqueue(server_id, release_tick);
+            let servers: Vec<_> = server_ids
+                .iter()
+                .map(|id| {
+                    self.server_queue
+                        .waiting_server(*id)
+                        .expect("Server was checked to be waiting")
+                })
+                .collect();
+            let handled = {
+                let borrowed: Vec<_> = servers.iter().map(|server| server.borrow()).collect();
+                let servers: Vec<&Server> = borrowed.iter().map(|server| server.server()).collect();
+                self.request_queue
+                    .handle_request(request_id, self.tick, &servers)
+            };
+            let Ok(release_tick) = handled else {
+                continue;
+            };
+
+            for server_id in server_ids {
+                self.server_queue
+                    .enqueue(server_id, self.tick, release_tick)
+                    .expect("Server was checked to be waiting");
+
+                if self.record_routes {
+                    self.routes.push((self.tick, request_id, server_id));
+                }
+            }
         }
     }
 
     fn increment_tick(&mut self) -> bool {
+        if let EndCondition::AfterRequests(requests) = self.end_condition {
+            if self.request_queue.resolved_count() >= requests {
+                self.running = false;
+                self.end = self.tick;
+                return self.running;
+            }
+        }
+
         // In order to allow custom routing options, we need to always tick with `tick_size` if
-        // there are requests waiting for servers. If there are no requests waiting, then we can
-        // directly advance the tick to the next request in the `request_buffer`, or the `server` in
-        // the `server_buffer`.
-        self.tick = if self.request_queue.has_waiting() {
+        // there are requests waiting for servers, stopping early at any tick a request abandons.
+        // If there are no requests waiting, then we can directly advance the tick to the next
+        // request in the `request_buffer`, or the `server` in the `server_buffer`.
+        // `TickMode::Fixed` always ticks with `tick_size`.
+        self.tick = if self.tick_mode == TickMode::Fixed {
             self.tick + self.tick_size
+        } else if self.request_queue.has_waiting() {
+            if self.server_queue.is_unstaffed() {
+                self.next_unstaffed_tick()
+            } else {
+                self.next_waiting_tick()
+            }
         } else {
             let request_buffer_head = self.request_queue.next_tick();
             let server_buffer_head = self.server_queue.next_tick();
@@ -240,34 +777,63 @@ impl Simulation {
 
         self.running
     }
+
+    /// With requests waiting, steps by `tick_size`, but never past the tick a waiting request
+    /// abandons at. Abandons are then exact, rather than overcounting the wait time by up to
+    /// `tick_size`.
+    fn next_waiting_tick(&self) -> Duration {
+        let step = self.tick + self.tick_size;
+        self.request_queue
+            .next_abandon_tick(self.tick)
+            .map_or(step, |abandon_tick| abandon_tick.min(step))
+    }
+
+    /// With requests waiting but no servers, nothing can change until a request abandons or
+    /// another arrives. Rather than stepping by `tick_size`, this skips to the first tick stepping
+    /// would have reached at or after the arrival, or to the abandon if sooner, so requests
+    /// abandon at the same ticks as with `next_waiting_tick`.
+    fn next_unstaffed_tick(&self) -> Duration {
+        let step = self.tick + self.tick_size;
+        let next_arrival = self
+            .request_queue
+            .next_tick()
+            .unwrap_or(self.end)
+            .min(self.end);
+
+        let arrival_step = if next_arrival <= step || self.tick_size.is_zero() {
+            step
+        } else {
+            let size = self.tick_size.as_nanos();
+            let steps = (next_arrival - self.tick).as_nanos().div_ceil(size);
+            u64::try_from(self.tick.as_nanos() + steps * size)
+                .map_or(self.end, Duration::from_nanos)
+        };
+
+        self.request_queue
+            .next_abandon_tick(self.tick)
+            .map_or(arrival_step, |abandon_tick| abandon_tick.min(arrival_step))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::request::Status;
-    use alloc::rc::Rc;
+    use crate::arrival::Batch;
+    use crate::request::{status_counts, AbandonReason, Status};
+    use alloc::{collections::BTreeMap, rc::Rc};
     use core::cell::RefCell;
     use rand::rngs::mock::StepRng;
-    use std::collections::HashMap;
 
     const TICK_SIZE: Duration = Duration::new(0, 50_000_000);
     const ONE_HOUR: Duration = Duration::new(60 * 60, 0);
 
-    fn request_stats(requests: &[Rc<RefCell<Request>>]) -> HashMap<Status, usize> {
-        requests.iter().fold(HashMap::new(), |mut acc, r| {
-            let status = *r.borrow().status();
-            if let Some(v) = acc.get_mut(&status) {
-                *v += 1;
-            } else {
-                acc.insert(status, 1);
-            }
-            acc
-        })
+    fn request_stats(requests: &[Rc<RefCell<Request>>]) -> BTreeMap<Status, usize> {
+        let data: Vec<_> = requests.iter().map(|r| r.borrow().data()).collect();
+        status_counts(&data)
     }
 
-    fn mock_rng() -> Box<dyn RngCore> {
+    fn mock_rng() -> Box<dyn RngCore + Send> {
         // Set the step size to be compatible with `gen_range`. `gen_range` restricts the domain by
         // giving multiple rolls per element in the range sequentially.
         let step = u64::MAX / 3600;
@@ -293,40 +859,1371 @@ mod tests {
     }
 
     #[test]
-    fn no_servers() -> Result<()> {
+    fn ticks_until_end() -> Result<()> {
         let mut sim = simulation();
+        assert!(matches!(sim.ticks(), Err(Error::NotRunning)));
 
-        let client = Client::default();
-        sim.add_client(client)?;
-
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
         sim.enable()?;
 
-        while sim.tick() {}
+        let ticks: Vec<Duration> = sim.ticks()?.collect();
 
-        let stats = request_stats(sim.request_queue.requests());
-        assert_eq!(None, stats.get(&Status::Answered));
-        assert_eq!(Some(&1), stats.get(&Status::Abandoned));
-        Ok(assert_eq!((false, ONE_HOUR), sim.running()))
+        assert!(ticks.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(Some(&ONE_HOUR), ticks.last());
+        Ok(assert!(matches!(sim.ticks(), Err(Error::NotRunning))))
     }
 
     #[test]
-    fn can_handle_requests() -> Result<()> {
-        let mut sim = simulation();
+    fn adding_a_client_keeps_earlier_requests() -> Result<()> {
+        let starts = |clients: usize| -> Result<Vec<Duration>> {
+            let mut sim = simulation();
+            sim.add_clients(vec![Client::default(); clients])?;
+            sim.enable()?;
+
+            Ok(sim
+                .request_queue
+                .requests()
+                .iter()
+                .map(|request| request.borrow().start())
+                .collect())
+        };
+
+        let (two, three) = (starts(2)?, starts(3)?);
+
+        assert_ne!(two[0], two[1]);
+        Ok(assert_eq!(two[..], three[..2]))
+    }
+
+    #[test]
+    fn no_servers() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client::default();
+        sim.add_client(client)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(None, stats.get(&Status::Answered));
+        assert_eq!(Some(&1), stats.get(&Status::Abandoned));
+        Ok(assert_eq!((false, ONE_HOUR), sim.running()))
+    }
+
+    #[test]
+    fn can_handle_requests() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client::default();
+        sim.add_client(client)?;
+
+        let server = Server::default();
+        sim.add_server(server)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(Some(&1), stats.get(&Status::Answered));
+        assert_eq!(None, stats.get(&Status::Abandoned));
+        Ok(assert_eq!((false, ONE_HOUR), sim.running()))
+    }
+
+    #[test]
+    fn records_serving_server() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_client(Client {
+            channel: ContactType::Email,
+            ..Client::default()
+        })?;
+
+        let server = Server {
+            channels: vec![ContactType::Call],
+            ..Server::default()
+        };
+        let server_id = server.id();
+        sim.add_server(server)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert_eq!(Status::Answered, data[0].status);
+        assert_eq!(Some(server_id), data[0].served_by);
+        assert_eq!(Status::Abandoned, data[1].status);
+        Ok(assert_eq!(None, data[1].served_by))
+    }
+
+    #[test]
+    fn records_server_idle_time() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client {
+            handle_time: Duration::new(60, 0),
+            ..Client::default()
+        };
+        sim.add_client(client)?;
+        sim.add_server(Server::default())?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let mut idle_times: Vec<Duration> =
+            sim.server_stats().iter().map(server::Stats::idle).collect();
+        idle_times.sort();
+        Ok(assert_eq!(
+            vec![ONE_HOUR - Duration::new(60, 0), ONE_HOUR],
+            idle_times
+        ))
+    }
+
+    #[test]
+    fn efficient_servers_handle_faster() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client::default();
+        let handle_time = client.handle_time;
+        sim.add_client(client)?;
+
+        let server = Server {
+            efficiency: 0.9,
+            ..Server::default()
+        };
+        sim.add_server(server)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert_eq!(Status::Answered, data[0].status);
+        Ok(assert_eq!(
+            Some(handle_time.mul_f64(0.9)),
+            data[0].handle_time
+        ))
+    }
+
+    #[test]
+    fn only_handles_eligible_channels() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client {
+            channel: ContactType::Call,
+            ..Client::default()
+        };
+        sim.add_client(client)?;
+
+        let server = Server {
+            channels: vec![ContactType::Email],
+            ..Server::default()
+        };
+        sim.add_server(server)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(None, stats.get(&Status::Answered));
+        Ok(assert_eq!(Some(&1), stats.get(&Status::Abandoned)))
+    }
+
+    #[test]
+    fn config_can_move_between_threads() {
+        let mut config = Config::new(ONE_HOUR, TICK_SIZE, mock_rng());
+        config.add_client(Client::default());
+        config.add_server(Server::default());
+
+        let answered = std::thread::spawn(move || -> Result<usize> {
+            let mut sim = Simulation::from(config);
+            sim.enable()?;
+
+            while sim.tick() {}
+
+            let stats = request_stats(sim.request_queue.requests());
+            Ok(stats.get(&Status::Answered).copied().unwrap_or_default())
+        })
+        .join()
+        .expect("Simulation thread should not panic");
+
+        assert!(matches!(answered, Ok(1)));
+    }
+
+    #[test]
+    fn can_bulk_add() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(), Client::default()])?;
+        sim.add_servers(vec![Server::default(), Server::default()])?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        Ok(assert_eq!(Some(&2), stats.get(&Status::Answered)))
+    }
+
+    #[test]
+    fn cannot_bulk_add_whilst_running() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.enable()?;
+
+        assert!(matches!(
+            sim.add_clients(vec![Client::default()]),
+            Err(Error::Enabled(Operation::AddClients))
+        ));
+        Ok(assert!(matches!(
+            sim.add_servers(vec![Server::default()]),
+            Err(Error::Enabled(Operation::AddServers))
+        )))
+    }
+
+    #[test]
+    fn rejects_setup_whilst_running_by_operation() -> Result<()> {
+        let mut sim = simulation();
+        sim.enable()?;
+
+        let rejected = |result: Result<()>| match result {
+            Err(Error::Enabled(operation)) => Some(operation),
+            _ => None,
+        };
+
+        assert_eq!(
+            Some(Operation::AddServer),
+            rejected(sim.add_server(Server::default()))
+        );
+        assert_eq!(
+            Some(Operation::AddClient),
+            rejected(sim.add_client(Client::default()))
+        );
+        assert_eq!(
+            Some(Operation::SetRoutingStrategy),
+            rejected(sim.set_routing_strategy(Box::new(Greedy)))
+        );
+        assert_eq!(
+            Some(Operation::SetTickMode),
+            rejected(sim.set_tick_mode(TickMode::Fixed))
+        );
+        assert_eq!(
+            Some(Operation::SetRecordOccupancy),
+            rejected(sim.set_record_occupancy(true))
+        );
+        assert_eq!(
+            Some(Operation::SetRecordRoutes),
+            rejected(sim.set_record_routes(true))
+        );
+        assert_eq!(
+            Some(Operation::SetRecordQueueDepth),
+            rejected(sim.set_record_queue_depth(true))
+        );
+        assert_eq!(
+            Some(Operation::SetEndCondition),
+            rejected(sim.set_end_condition(EndCondition::AfterRequests(1)))
+        );
+        assert_eq!(
+            Some(Operation::SetArrivalSeed),
+            rejected(sim.set_arrival_seed(1))
+        );
+        assert_eq!(
+            Some(Operation::SetServiceSeed),
+            rejected(sim.set_service_seed(1))
+        );
+        assert_eq!(Some(Operation::Generate), rejected(sim.generate()));
+        Ok(assert_eq!(
+            Some(Operation::Enable),
+            rejected(sim.enable().map(|_| ()))
+        ))
+    }
+
+    #[test]
+    fn can_build_simulation() -> Result<()> {
+        let mut sim = SimulationBuilder::new(ONE_HOUR, TICK_SIZE, mock_rng())
+            .clients(vec![Client::default(), Client::default()])
+            .servers(vec![Server::default(), Server::default()])
+            .routing_strategy(Box::new(Greedy))
+            .record_occupancy(true)
+            .record_queue_depth(true)
+            .build()?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(Some(&2), stats.get(&Status::Answered));
+        assert!(!sim.queue_depth_series().is_empty());
+        Ok(assert!(!sim.occupancy_series().is_empty()))
+    }
+
+    #[test]
+    fn cannot_build_with_zero_tick_size() {
+        let sim = SimulationBuilder::new(ONE_HOUR, Duration::ZERO, mock_rng()).build();
+
+        assert!(matches!(sim, Err(Error::ZeroTickSize)));
+    }
+
+    #[test]
+    fn can_set_routing_strategy() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
+        sim.set_routing_strategy(Box::new(routing::WeightedRandom::new(
+            routing::Weighting::IdleTime,
+        )))?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        Ok(assert_eq!(Some(&1), stats.get(&Status::Answered)))
+    }
+
+    #[test]
+    fn conference_waits_for_servers() -> Result<()> {
+        let mut sim = simulation();
+
+        // The first request holds a server, so the conference must wait for it to be released
+        let single = Client {
+            abandon_time: ONE_HOUR,
+            ..Client::default()
+        };
+        let conference = Client {
+            required_servers: 2,
+            ..single.clone()
+        };
+        sim.add_client(single)?;
+        sim.add_client(conference)?;
+
+        sim.add_server(Server::default())?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert_eq!(Status::Answered, data[0].status);
+        assert_eq!(Status::Answered, data[1].status);
+        Ok(assert!(data[1].wait_time > Some(Duration::ZERO)))
+    }
+
+    #[test]
+    fn clamps_handling_at_end() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client {
+            handle_time: 2 * ONE_HOUR,
+            abandon_time: ONE_HOUR,
+            ..Client::default()
+        })?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert_eq!(Status::Answered, data[0].status);
+        assert!(data[0].handle_time <= Some(ONE_HOUR));
+
+        let stats = sim.server_stats();
+        Ok(assert!(stats[0].busy <= stats[0].available))
+    }
+
+    #[test]
+    fn no_op_routing_abandons_every_request() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 2])?;
+        sim.add_servers(vec![Server::default(), Server::default()])?;
+        sim.set_routing_strategy(Box::new(routing::NoOp))?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(None, stats.get(&Status::Answered));
+        Ok(assert_eq!(Some(&2), stats.get(&Status::Abandoned)))
+    }
+
+    #[test]
+    fn random_routing_is_reproducible() -> Result<()> {
+        let run = || -> Result<Vec<(Duration, usize, usize)>> {
+            let mut sim = simulation();
+
+            sim.add_clients(vec![Client::default(); 10])?;
+            sim.add_servers(vec![Server::default(), Server::default()])?;
+            sim.set_routing_strategy(Box::new(routing::Random))?;
+            sim.set_record_routes(true)?;
+
+            sim.enable()?;
+
+            while sim.tick() {}
+
+            let servers = sim.server_stats();
+            let requests: Vec<usize> = sim
+                .request_queue
+                .requests()
+                .iter()
+                .map(|request| request.borrow().id())
+                .collect();
+
+            // Ids differ between simulations, so compare by position
+            Ok(sim
+                .routes()
+                .iter()
+                .map(|(tick, request_id, server_id)| {
+                    let request = requests.iter().position(|id| id == request_id);
+                    let server = servers.iter().position(|s| s.id == *server_id);
+                    (*tick, request.unwrap(), server.unwrap())
+                })
+                .collect())
+        };
+
+        let routes = run()?;
+        assert!(!routes.is_empty());
+        Ok(assert_eq!(routes, run()?))
+    }
+
+    #[test]
+    fn takes_each_completed_request_once() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 10])?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        let mut completed = Vec::new();
+        while sim.tick() {
+            completed.extend(sim.take_completed());
+        }
+        completed.extend(sim.take_completed());
+
+        let stats = request_stats(sim.request_queue.requests());
+        let expected = stats.get(&Status::Answered).unwrap_or(&0)
+            + stats.get(&Status::Abandoned).unwrap_or(&0);
+        assert!(sim.take_completed().is_empty());
+        Ok(assert_eq!(expected, completed.len()))
+    }
+
+    #[test]
+    fn totals_requests_by_outcome() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 10])?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let totals = sim.totals();
+        let stats = request_stats(sim.request_queue.requests());
+        assert_eq!(10, totals.offered);
+        assert_eq!(
+            stats.get(&Status::Answered).copied().unwrap_or(0),
+            totals.answered
+        );
+        assert_eq!(
+            stats.get(&Status::Abandoned).copied().unwrap_or(0),
+            totals.abandoned
+        );
+        assert_eq!(
+            totals.offered,
+            totals.answered + totals.abandoned + totals.pending
+        );
+        Ok(assert_eq!(
+            totals,
+            request::RunTotals::from(&sim.request_data()[..])
+        ))
+    }
+
+    #[test]
+    fn ends_after_requests_are_resolved() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 10])?;
+        sim.add_server(Server::default())?;
+        sim.set_end_condition(EndCondition::AfterRequests(5))?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let totals = sim.totals();
+        let (running, tick) = sim.running();
+        assert!(!running);
+        assert!(tick < ONE_HOUR);
+        assert_eq!(10, totals.offered);
+        Ok(assert_eq!(5, totals.answered + totals.abandoned))
+    }
+
+    #[test]
+    fn reads_back_horizon() {
+        let sim = simulation();
+
+        assert_eq!(Duration::ZERO, sim.start());
+        assert_eq!(ONE_HOUR, sim.end());
+        assert_eq!(TICK_SIZE, sim.tick_size());
+    }
+
+    #[test]
+    fn ends_at_tick() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.set_end_condition(EndCondition::AtTick(ONE_HOUR / 2))?;
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        Ok(assert_eq!((false, ONE_HOUR / 2), sim.running()))
+    }
+
+    #[test]
+    fn callbacks_wait_rather_than_abandon() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client {
+            callback_rate: 1.0,
+            ..Client::default()
+        };
+        sim.add_clients(vec![client; 20])?;
+        sim.add_server(Server::default())?;
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert!(data.iter().all(|d| Status::Abandoned != d.status));
+        Ok(assert!(data.iter().any(
+            |d| Status::Answered == d.status && d.callback_time.is_some()
+        )))
+    }
+
+    #[test]
+    fn transfers_spawn_one_follow_up_per_answer() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client {
+            transfer_rate: 1.0,
+            ..Client::default()
+        };
+        sim.add_clients(vec![client; 20])?;
+        sim.add_servers(vec![Server::default(); 5])?;
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        let (follow_ups, originals): (Vec<_>, Vec<_>) =
+            data.iter().partition(|d| d.transfer_of.is_some());
+        let answered: Vec<_> = originals
+            .iter()
+            .filter(|d| Status::Answered == d.status)
+            .collect();
+
+        assert_eq!(20, originals.len());
+        assert!(!answered.is_empty());
+        assert!(answered.iter().all(|d| d.transferred_to.is_some()));
+        assert!(follow_ups.iter().all(|d| d.transferred_to.is_none()));
+        Ok(assert_eq!(answered.len(), follow_ups.len()))
+    }
+
+    #[test]
+    fn generates_requests_before_enabling() -> Result<()> {
+        let mut sim = simulation();
+        sim.add_clients(vec![Client::default(); 10])?;
+        sim.add_server(Server::default())?;
+
+        sim.generate()?;
+        let pending = sim.pending_requests();
+        sim.generate()?;
+
+        assert_eq!(10, pending.len());
+        assert!(pending.windows(2).all(|w| w[0].start <= w[1].start));
+        assert!(pending.iter().all(|d| d.start <= ONE_HOUR));
+        assert_eq!(
+            pending.iter().map(|d| d.id).collect::<Vec<_>>(),
+            sim.pending_requests()
+                .iter()
+                .map(|d| d.id)
+                .collect::<Vec<_>>()
+        );
+
+        sim.enable()?;
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert_eq!(10, data.len());
+        assert!(sim.pending_requests().is_empty());
+        Ok(assert!(data.iter().all(|d| pending
+            .iter()
+            .any(|p| p.id == d.id && p.start == d.start))))
+    }
+
+    #[test]
+    fn regenerates_the_same_arrivals_after_setup() -> Result<()> {
+        let starts = |sim: &Simulation| -> Vec<Duration> {
+            sim.pending_requests().iter().map(|d| d.start).collect()
+        };
+
+        let mut generated = simulation();
+        generated.add_client(Client::default())?;
+        generated.generate()?;
+        generated.add_client(Client::default())?;
+        generated.generate()?;
+
+        let mut direct = simulation();
+        direct.add_clients(vec![Client::default(); 2])?;
+        direct.generate()?;
+
+        Ok(assert_eq!(starts(&direct), starts(&generated)))
+    }
+
+    fn abandons_and_ticks(servers: Vec<Server>) -> Result<(Vec<request::Data>, usize)> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 50])?;
+        sim.add_servers(servers)?;
+        sim.enable()?;
+
+        let mut ticks = 0;
+        while sim.tick() {
+            ticks += 1;
+        }
+
+        let mut data = sim.request_data();
+        data.sort_unstable_by_key(|d| d.start);
+        Ok((data, ticks))
+    }
+
+    #[test]
+    fn skips_between_abandons_without_servers() -> Result<()> {
+        // A server which can't handle any request waits, so the simulation steps by `tick_size`
+        let email = Server {
+            channels: vec![ContactType::Email],
+            ..Server::default()
+        };
+        let (stepped, stepped_ticks) = abandons_and_ticks(vec![email])?;
+        let (skipped, skipped_ticks) = abandons_and_ticks(Vec::new())?;
+
+        let outcome = |data: &[request::Data]| -> Vec<_> {
+            data.iter()
+                .map(|d| (d.start, d.status, d.wait_time, d.abandon_reason))
+                .collect()
+        };
+        assert_eq!(outcome(&stepped), outcome(&skipped));
+        assert!(skipped.iter().all(|d| d.status == Status::Abandoned));
+        Ok(assert!(skipped_ticks * 100 < stepped_ticks))
+    }
+
+    #[test]
+    fn abandons_between_steps_are_exact() -> Result<()> {
+        // Abandons fall between steps of a coarse `tick_size`, whilst requests wait for a server
+        // which can't handle them
+        let mut sim = Simulation::new(ONE_HOUR, Duration::from_secs(7), mock_rng());
+        let abandon_time = Duration::from_secs(30);
+        let client = Client {
+            abandon_time,
+            ..Client::default()
+        };
+        sim.add_clients(vec![client; 50])?;
+        sim.add_server(Server {
+            channels: vec![ContactType::Email],
+            ..Server::default()
+        })?;
+        sim.enable()?;
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        assert!(data.iter().all(|d| d.status == Status::Abandoned));
+        Ok(assert!(data
+            .iter()
+            .all(|d| d.wait_time == Some(abandon_time))))
+    }
+
+    #[test]
+    fn generates_batched_arrivals() -> Result<()> {
+        let mut sim = simulation();
+        let interval = Duration::from_secs(5 * 60);
+        let client = Client {
+            batch: Some(Batch { size: 10, interval }),
+            ..Client::default()
+        };
+
+        sim.add_clients(vec![client; 60])?;
+        sim.generate()?;
+
+        let mut batches: BTreeMap<Duration, usize> = BTreeMap::new();
+        for data in sim.pending_requests() {
+            *batches.entry(data.start).or_default() += 1;
+        }
+
+        assert!(batches.values().all(|size| (1..=10).contains(size)));
+        assert!(batches.len() >= 6);
+        assert!(batches
+            .keys()
+            .all(|start| start.as_nanos() % interval.as_nanos() == 0));
+        Ok(assert!(batches
+            .keys()
+            .zip(batches.keys().skip(1))
+            .all(|(a, b)| *b - *a >= interval)))
+    }
+
+    fn answered_with_availability(availability: f64) -> Result<usize> {
+        let mut sim = Simulation::new(ONE_HOUR, TICK_SIZE, Box::new(SmallRng::seed_from_u64(7)));
+        // Requests only wait for a single routing opportunity, and are handled within a tick
+        let client = Client {
+            handle_time: Duration::from_millis(10),
+            abandon_time: Duration::ZERO,
+            ..Client::default()
+        };
+
+        sim.add_clients(vec![client; 2000])?;
+        sim.add_server(Server {
+            availability,
+            ..Server::default()
+        })?;
+        sim.enable()?;
+        while sim.tick() {}
+
+        Ok(sim
+            .request_data()
+            .iter()
+            .filter(|d| d.status == Status::Answered)
+            .count())
+    }
+
+    #[test]
+    fn part_time_servers_answer_in_proportion() -> Result<()> {
+        let full = answered_with_availability(1.0)?;
+        let half = answered_with_availability(0.5)?;
+
+        assert!(full > 1900, "full time answered {full}");
+        // Between 45% and 55% of the full time server
+        Ok(assert!(
+            (full * 45..full * 55).contains(&(half * 100)),
+            "half time answered {half} of {full}"
+        ))
+    }
+
+    #[test]
+    fn pre_queue_drops_abandon_without_waiting() -> Result<()> {
+        let mut sim = simulation();
+        let client = Client {
+            pre_queue_drop_rate: 1.0,
+            ..Client::default()
+        };
+
+        sim.add_clients(vec![client; 10])?;
+        sim.add_server(Server::default())?;
+        sim.enable()?;
+        assert!(sim.pending_requests().is_empty());
+        assert_eq!(10, sim.take_completed().len());
+        while sim.tick() {}
+
+        let dropped = |d: &request::Data| {
+            d.status == Status::Abandoned
+                && d.abandon_reason == Some(AbandonReason::PreQueue)
+                && d.wait_time == Some(Duration::ZERO)
+                && d.served_by.is_none()
+        };
+        Ok(assert!(sim.request_data().iter().all(dropped)))
+    }
+
+    #[test]
+    fn counts_calls_per_server() -> Result<()> {
+        let mut sim = simulation();
+        let client = |channel: ContactType| Client {
+            channel,
+            handle_time: Duration::from_secs(1),
+            ..Client::default()
+        };
+        let server = |channel: ContactType| Server {
+            channels: vec![channel],
+            ..Server::default()
+        };
+        let calls = server(ContactType::Call);
+        let emails = server(ContactType::Email);
+        let ids = (calls.id(), emails.id());
+
+        sim.add_clients(vec![client(ContactType::Call); 6])?;
+        sim.add_clients(vec![client(ContactType::Email); 2])?;
+        sim.add_servers(vec![calls, emails])?;
+        sim.enable()?;
+        while sim.tick() {}
+
+        assert!(sim
+            .request_data()
+            .iter()
+            .all(|d| d.status == Status::Answered));
+        Ok(assert_eq!(
+            vec![(ids.0, 6), (ids.1, 2)],
+            sim.server_call_counts()
+        ))
+    }
+
+    #[test]
+    fn spreads_requests_across_idle_servers() -> Result<()> {
+        let mut sim = simulation();
+        let client = Client {
+            handle_time: Duration::from_secs(1),
+            ..Client::default()
+        };
+        sim.add_clients(vec![client; 100])?;
+        sim.add_servers((0..5).map(|_| Server::default()).collect())?;
+        sim.enable()?;
+        while sim.tick() {}
+
+        let counts: Vec<usize> = sim.server_call_counts().iter().map(|(_, n)| *n).collect();
+        Ok(assert_eq!(vec![20; 5], counts))
+    }
+
+    #[test]
+    fn estimates_waiting_and_in_progress_work() -> Result<()> {
+        let mut sim = simulation();
+        let client = Client {
+            handle_time: ONE_HOUR,
+            abandon_time: ONE_HOUR,
+            ..Client::default()
+        };
+
+        sim.add_clients(vec![client; 2])?;
+        sim.add_server(Server::default())?;
+        sim.enable()?;
+        assert_eq!(Duration::ZERO, sim.workload_estimate());
+
+        // Until one request is being handled whilst the other waits for the server
+        let statuses = |sim: &Simulation| sim.request_data().iter().map(|d| d.status).collect();
+        while sim.tick() {
+            let mut statuses: Vec<Status> = statuses(&sim);
+            statuses.sort_unstable();
+            if statuses == [Status::Enqueued, Status::Answered] {
+                break;
+            }
+        }
+
+        let (running, tick) = sim.running();
+        assert!(running);
+        let data = sim.request_data();
+        let answered = data
+            .iter()
+            .find(|d| d.status == Status::Answered)
+            .expect("A request is being handled");
+        let release = answered.start + answered.wait_time.unwrap() + ONE_HOUR;
+
+        Ok(assert_eq!(
+            ONE_HOUR + (release - tick),
+            sim.workload_estimate()
+        ))
+    }
+
+    #[test]
+    fn estimates_no_waiting_work_for_a_request_just_answered() -> Result<()> {
+        let mut sim = simulation();
+        sim.set_tick_mode(TickMode::Fixed)?;
+        sim.add_client(Client {
+            handle_time: ONE_HOUR,
+            ..Client::default()
+        })?;
+        sim.add_server(Server::default())?;
+        sim.enable()?;
+
+        while sim.request_data()[0].status != Status::Answered {
+            assert!(sim.tick());
+        }
+
+        let (_, tick) = sim.running();
+        let data = sim.request_data();
+        let release = data[0].start + data[0].wait_time.unwrap() + ONE_HOUR;
+        Ok(assert_eq!(release - tick, sim.workload_estimate()))
+    }
+
+    #[test]
+    fn ids_are_unique_within_a_run() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 100])?;
+        sim.enable()?;
+
+        let mut request_ids: Vec<usize> = sim.request_data().iter().map(|d| d.id).collect();
+        request_ids.sort_unstable();
+        request_ids.dedup();
+
+        Ok(assert_eq!(100, request_ids.len()))
+    }
+
+    #[test]
+    fn errors_when_stalled() -> Result<()> {
+        let mut sim = Simulation::new(ONE_HOUR, Duration::ZERO, mock_rng());
+
+        // A waiting request with a zero tick size never advances
+        sim.add_client(Client::default())?;
+        sim.enable()?;
+
+        let mut ticks = 0;
+        let err = loop {
+            match sim.try_tick() {
+                Ok(true) => ticks += 1,
+                Ok(false) => panic!("Simulation completed without stalling"),
+                Err(err) => break err,
+            }
+        };
+
+        assert!(ticks < 2 * STALL_LIMIT);
+        assert!(matches!(err, Error::Stalled(tick) if tick == sim.running().1));
+        Ok(assert!(matches!(sim.try_tick(), Err(Error::Stalled(_)))))
+    }
+
+    #[test]
+    #[should_panic(expected = "stalled")]
+    fn tick_panics_when_stalled() {
+        let mut sim = Simulation::new(ONE_HOUR, Duration::ZERO, mock_rng());
+        sim.add_client(Client::default()).unwrap();
+        sim.enable().unwrap();
+
+        while sim.tick() {}
+    }
+
+    /// Routes like `Greedy`, but first routes a request id which is not waiting to each server.
+    struct Stale;
+
+    impl RoutingStrategy for Stale {
+        fn route(
+            &mut self,
+            tick: Duration,
+            requests: Vec<&RequestData>,
+            servers: Vec<&ServerData>,
+            rng: &mut dyn RngCore,
+        ) -> Vec<(usize, usize)> {
+            let mut routes: Vec<(usize, usize)> = servers
+                .iter()
+                .map(|server| (usize::MAX, server.id))
+                .collect();
+            routes.extend(Greedy.route(tick, requests, servers, rng));
+            routes
+        }
+    }
+
+    /// Routes like `Greedy`, tagging the first request routed on each tick as `"escalated"`.
+    #[derive(Default)]
+    struct Escalating {
+        tags: Vec<(usize, &'static str)>,
+    }
+
+    impl RoutingStrategy for Escalating {
+        fn route(
+            &mut self,
+            tick: Duration,
+            requests: Vec<&RequestData>,
+            servers: Vec<&ServerData>,
+            rng: &mut dyn RngCore,
+        ) -> Vec<(usize, usize)> {
+            let routes = Greedy.route(tick, requests, servers, rng);
+            if let Some((request_id, _)) = routes.first() {
+                self.tags.push((*request_id, "escalated"));
+            }
+            routes
+        }
+
+        fn take_tags(&mut self) -> Vec<(usize, &'static str)> {
+            core::mem::take(&mut self.tags)
+        }
+    }
+
+    #[test]
+    fn strategies_tag_requests() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_clients(vec![Client::default(); 5])?;
+        sim.add_server(Server::default())?;
+        sim.set_routing_strategy(Box::<Escalating>::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let data = sim.request_data();
+        let escalated = data
+            .iter()
+            .filter(|d| d.tags.contains(&"escalated"))
+            .collect::<Vec<_>>();
+        assert!(!escalated.is_empty());
+        Ok(assert!(escalated.iter().all(|d| Status::Answered
+            == d.status
+            && d.tags == ["escalated"])))
+    }
+
+    #[test]
+    fn skips_stale_routes() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
+        sim.set_routing_strategy(Box::new(Stale))?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        Ok(assert!(stats.contains_key(&Status::Answered)))
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn arrival_and_service_seeds_are_independent() -> Result<()> {
+        // Without servers every request abandons, so the wait time is the sampled abandon time
+        let run = |service_seed: u64| -> Result<(Vec<Duration>, Vec<Option<Duration>>)> {
+            let mut sim = simulation();
+
+            let client = Client {
+                abandon_distribution: distribution::Distribution::Exponential,
+                ..Client::default()
+            };
+            sim.add_clients(vec![client; 10])?;
+            sim.set_arrival_seed(7)?;
+            sim.set_service_seed(service_seed)?;
+
+            sim.enable()?;
+
+            while sim.tick() {}
+
+            let starts = sim
+                .request_queue
+                .requests()
+                .iter()
+                .map(|request| request.borrow().start())
+                .collect();
+            let waits = sim.request_data().iter().map(|d| d.wait_time).collect();
+            Ok((starts, waits))
+        };
+
+        let (starts, waits) = run(1)?;
+        let (other_starts, other_waits) = run(2)?;
+
+        assert_eq!(starts, other_starts);
+        Ok(assert_ne!(waits, other_waits))
+    }
+
+    #[test]
+    fn surge_server_rescues_waiting_requests() -> Result<()> {
+        let run = |surge: bool| -> Result<BTreeMap<Status, usize>> {
+            let mut sim = simulation();
+
+            let client = Client {
+                abandon_time: Duration::from_secs(600),
+                ..Client::default()
+            };
+            sim.add_clients(vec![client; 2])?;
+            assert!(matches!(
+                sim.add_server_live(Server::default()),
+                Err(Error::NotRunning)
+            ));
+
+            sim.enable()?;
+
+            while sim.request_queue.waiting_count() == 0 {
+                assert!(sim.tick());
+            }
+            if surge {
+                sim.add_server_live(Server::default())?;
+            }
+
+            while sim.tick() {}
+
+            Ok(request_stats(sim.request_queue.requests()))
+        };
+
+        assert_eq!(Some(&2), run(false)?.get(&Status::Abandoned));
+        Ok(assert_eq!(Some(&2), run(true)?.get(&Status::Answered)))
+    }
+
+    #[test]
+    fn removes_idle_server_immediately() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        let server = Server::default();
+        let id = server.id();
+        sim.add_server(server)?;
+
+        sim.enable()?;
+        sim.remove_server(id)?;
+
+        assert!(sim.server_queue.routing_data(sim.rng.as_mut()).is_empty());
+        assert!(matches!(
+            sim.remove_server(id),
+            Err(Error::UnknownServer(_))
+        ));
+
+        while sim.tick() {}
+
+        let stats = request_stats(sim.request_queue.requests());
+        Ok(assert_eq!(None, stats.get(&Status::Answered)))
+    }
+
+    #[test]
+    fn removes_busy_server_after_release() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        let server = Server::default();
+        let id = server.id();
+        sim.add_server(server)?;
+
+        sim.enable()?;
+
+        while sim.request_data()[0].status != Status::Answered {
+            assert!(sim.tick());
+        }
+        sim.remove_server(id)?;
+
+        while sim.tick() {}
+
+        assert!(sim.server_queue.routing_data(sim.rng.as_mut()).is_empty());
+        Ok(assert!(matches!(
+            sim.remove_server(id),
+            Err(Error::UnknownServer(_))
+        )))
+    }
+
+    #[test]
+    fn conference_occupies_servers() -> Result<()> {
+        let mut sim = simulation();
+
+        let conference = Client {
+            required_servers: 2,
+            ..Client::default()
+        };
+        sim.add_client(conference)?;
+
+        sim.add_server(Server::default())?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.request_data()[0].status != Status::Answered {
+            assert!(sim.tick());
+        }
+
+        // Both servers are busy until the conference is released
+        assert!(sim.server_queue.routing_data(sim.rng.as_mut()).is_empty());
+
+        while sim.tick() {}
+
+        Ok(assert_eq!(
+            2,
+            sim.server_queue.routing_data(sim.rng.as_mut()).len()
+        ))
+    }
+
+    #[test]
+    fn conference_is_handled_at_the_pace_of_the_slowest_server() -> Result<()> {
+        for efficiencies in [[1.0, 2.0], [2.0, 1.0]] {
+            let mut sim = simulation();
+            sim.add_client(Client {
+                required_servers: 2,
+                clean_up_time: Duration::from_secs(60),
+                ..Client::default()
+            })?;
+
+            let servers: Vec<Server> = efficiencies
+                .iter()
+                .map(|efficiency| Server {
+                    efficiency: *efficiency,
+                    acw_factor: *efficiency,
+                    ..Server::default()
+                })
+                .collect();
+            let slowest = servers
+                .iter()
+                .max_by(|a, b| a.efficiency.total_cmp(&b.efficiency))
+                .map(Server::id);
+            sim.add_servers(servers)?;
+            sim.enable()?;
+            while sim.tick() {}
+
+            let data = &sim.request_data()[0];
+            assert_eq!(slowest, data.served_by);
+            assert_eq!(Some(Client::default().handle_time * 2), data.handle_time);
+            assert_eq!(Some(Duration::from_secs(120)), data.clean_up_time);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn records_occupancy() -> Result<()> {
+        let mut sim = simulation();
 
         let client = Client::default();
+        let handle_time = client.handle_time;
         sim.add_client(client)?;
+        sim.add_server(Server::default())?;
+
+        sim.set_record_occupancy(true)?;
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let start = sim.request_queue.requests()[0].borrow().start();
+        let series = sim.occupancy_series();
+        assert!(series.contains(&(start, 1)));
+        Ok(assert!(series.contains(&(start + handle_time, 0))))
+    }
+
+    #[test]
+    fn does_not_record_occupancy_by_default() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        Ok(assert!(sim.occupancy_series().is_empty()))
+    }
+
+    #[test]
+    fn records_routes() -> Result<()> {
+        let mut sim = simulation();
 
+        let client = Client {
+            abandon_time: ONE_HOUR,
+            ..Client::default()
+        };
+        let handle_time = client.handle_time;
+        sim.add_client(client.clone())?;
+        sim.add_client(client)?;
         let server = Server::default();
+        let server_id = server.id();
         sim.add_server(server)?;
 
+        sim.set_record_routes(true)?;
         sim.enable()?;
 
         while sim.tick() {}
 
-        let stats = request_stats(sim.request_queue.requests());
-        assert_eq!(Some(&1), stats.get(&Status::Answered));
-        assert_eq!(None, stats.get(&Status::Abandoned));
-        Ok(assert_eq!((false, ONE_HOUR), sim.running()))
+        let (first, second) = {
+            let requests = sim.request_queue.requests();
+            let request = |i: usize| {
+                let request = requests[i].borrow();
+                (request.start(), request.id())
+            };
+            (request(0), request(1))
+        };
+
+        let routes = sim.routes();
+        assert_eq!(2, routes.len());
+        assert_eq!((first.0, first.1, server_id), routes[0]);
+        assert_eq!((second.1, server_id), (routes[1].1, routes[1].2));
+        Ok(assert!(routes[1].0 >= first.0 + handle_time))
+    }
+
+    #[test]
+    fn does_not_record_routes_by_default() -> Result<()> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        Ok(assert!(sim.routes().is_empty()))
+    }
+
+    fn occupancy_steps(tick_mode: TickMode) -> Result<Vec<Duration>> {
+        let mut sim = simulation();
+
+        sim.add_client(Client::default())?;
+        sim.add_server(Server::default())?;
+        sim.set_record_occupancy(true)?;
+        sim.set_tick_mode(tick_mode)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        Ok(sim
+            .occupancy_series()
+            .windows(2)
+            .map(|samples| samples[1].0 - samples[0].0)
+            .collect())
+    }
+
+    #[test]
+    fn fixed_tick_mode_is_evenly_spaced() -> Result<()> {
+        let steps = occupancy_steps(TickMode::Fixed)?;
+
+        Ok(assert!(steps.iter().all(|step| *step == TICK_SIZE)))
+    }
+
+    #[test]
+    fn event_driven_tick_mode_skips_idle_ticks() -> Result<()> {
+        let steps = occupancy_steps(TickMode::EventDriven)?;
+
+        Ok(assert!(steps.iter().any(|step| *step > TICK_SIZE)))
+    }
+
+    #[test]
+    fn records_queue_depth() -> Result<()> {
+        let mut sim = simulation();
+
+        // Without servers both requests wait until they abandon, which is long enough to overlap
+        let client = Client {
+            abandon_time: Duration::from_secs(600),
+            ..Client::default()
+        };
+        sim.add_client(client.clone())?;
+        sim.add_client(client)?;
+        sim.set_record_queue_depth(true)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        let series = sim.queue_depth_series();
+        assert_eq!(Some(2), series.iter().map(|(_, depth)| *depth).max());
+        assert_eq!(2, sim.peak_queue_depth());
+        Ok(assert_eq!(Some(0), series.last().map(|(_, depth)| *depth)))
+    }
+
+    #[test]
+    fn tracks_peak_queue_depth_without_recording() -> Result<()> {
+        let mut sim = simulation();
+
+        let client = Client {
+            abandon_time: Duration::from_secs(600),
+            ..Client::default()
+        };
+        sim.add_client(client.clone())?;
+        sim.add_client(client)?;
+
+        sim.enable()?;
+
+        while sim.tick() {}
+
+        assert!(sim.queue_depth_series().is_empty());
+        Ok(assert_eq!(2, sim.peak_queue_depth()))
     }
 
     #[test]
@@ -381,4 +2278,12 @@ mod tests {
         let server = Server::default();
         Ok(assert!(sim.add_server(server).is_err()))
     }
+
+    #[test]
+    fn cannot_set_routing_strategy_whilst_running() -> Result<()> {
+        let mut sim = Simulation::new(ONE_HOUR, TICK_SIZE, mock_rng());
+        sim.enable()?;
+
+        Ok(assert!(sim.set_routing_strategy(Box::new(Greedy)).is_err()))
+    }
 }
@@ -1,9 +1,139 @@
-use crate::request::Status;
+use crate::request::{AbandonReason, Status};
+use crate::Attribute;
+use alloc::{collections::BTreeMap, vec::Vec};
 use core::time::Duration;
 
 pub struct Data {
     pub id: usize,
+    /// The tick the request arrives.
+    pub start: Duration,
+    /// The tick the request was enqueued to wait for a `Server`. `None` if it was never enqueued,
+    /// e.g. abandoned before being queued.
+    pub enqueued_at: Option<Duration>,
+    /// The tick the request stopped waiting, either answered or abandoned. `None` whilst waiting.
+    pub answered_or_abandoned_at: Option<Duration>,
     pub status: Status,
     pub wait_time: Option<Duration>,
     pub handle_time: Option<Duration>,
+    /// The time the serving `Server` spent cleaning up (ACW) after handling. `None` unless
+    /// answered.
+    pub clean_up_time: Option<Duration>,
+    /// The id of the `Server` which handled the request. `None` unless answered.
+    pub served_by: Option<usize>,
+    /// Why the request was abandoned. `None` unless abandoned.
+    pub abandon_reason: Option<AbandonReason>,
+    /// The time from `start` until the request reached its abandon time and opted into a
+    /// callback. `None` unless it opted into a callback.
+    pub callback_time: Option<Duration>,
+    /// The id of the request this is a follow-up of, when it was spawned by a transfer.
+    pub transfer_of: Option<usize>,
+    /// The id of the follow-up request spawned when this was answered and transferred.
+    pub transferred_to: Option<usize>,
+    /// The attributes a `Server` needed to handle the request, from the `Client` which made it.
+    pub required_attributes: Vec<Attribute>,
+    /// Annotations from the `RoutingStrategy`, e.g. `"escalated"`, in the order they were added.
+    pub tags: Vec<&'static str>,
+}
+
+/// Counts the number of requests in each `Status`. Statuses without any requests are omitted.
+#[must_use]
+pub fn status_counts(data: &[Data]) -> BTreeMap<Status, usize> {
+    data.iter().fold(BTreeMap::new(), |mut counts, d| {
+        *counts.entry(d.status).or_insert(0) += 1;
+        counts
+    })
+}
+
+/// The number of requests in a `Simulation` by outcome, collected from each request's `Status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RunTotals {
+    /// Every request, whatever its outcome.
+    pub offered: usize,
+    pub answered: usize,
+    pub abandoned: usize,
+    /// Requests which were neither answered nor abandoned, either still waiting or yet to arrive.
+    pub pending: usize,
+}
+
+impl FromIterator<Status> for RunTotals {
+    fn from_iter<I: IntoIterator<Item = Status>>(statuses: I) -> Self {
+        statuses
+            .into_iter()
+            .fold(Self::default(), |mut totals, status| {
+                totals.offered += 1;
+                match status {
+                    Status::Answered => totals.answered += 1,
+                    Status::Abandoned => totals.abandoned += 1,
+                    Status::Pending | Status::Enqueued => totals.pending += 1,
+                }
+                totals
+            })
+    }
+}
+
+impl From<&[Data]> for RunTotals {
+    fn from(data: &[Data]) -> Self {
+        data.iter().map(|d| d.status).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(status: Status) -> Data {
+        Data {
+            id: 0,
+            start: Duration::ZERO,
+            enqueued_at: None,
+            answered_or_abandoned_at: None,
+            status,
+            wait_time: None,
+            handle_time: None,
+            clean_up_time: None,
+            served_by: None,
+            abandon_reason: None,
+            callback_time: None,
+            transfer_of: None,
+            transferred_to: None,
+            required_attributes: Vec::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn counts_statuses() {
+        let data = [
+            data(Status::Answered),
+            data(Status::Abandoned),
+            data(Status::Answered),
+            data(Status::Enqueued),
+        ];
+
+        let counts = status_counts(&data);
+
+        assert_eq!(Some(&2), counts.get(&Status::Answered));
+        assert_eq!(Some(&1), counts.get(&Status::Abandoned));
+        assert_eq!(Some(&1), counts.get(&Status::Enqueued));
+        assert_eq!(None, counts.get(&Status::Pending));
+    }
+
+    #[test]
+    fn totals_statuses() {
+        let data = [
+            data(Status::Answered),
+            data(Status::Abandoned),
+            data(Status::Answered),
+            data(Status::Enqueued),
+            data(Status::Pending),
+        ];
+
+        let expected = RunTotals {
+            offered: 5,
+            answered: 2,
+            abandoned: 1,
+            pending: 2,
+        };
+        assert_eq!(expected, RunTotals::from(&data[..]));
+    }
 }
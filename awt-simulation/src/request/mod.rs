@@ -3,13 +3,13 @@ pub(crate) mod queue;
 
 use alloc::vec::Vec;
 use core::cmp::Ordering;
-use core::sync::{atomic, atomic::AtomicUsize};
 use core::time::Duration;
 
-use super::{Attribute, Client};
-pub use data::Data;
+use super::{Attribute, Client, ContactType, Server};
+pub use data::{status_counts, Data, RunTotals};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Status {
     Pending,
     Enqueued,
@@ -23,24 +23,75 @@ impl Default for Status {
     }
 }
 
-static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+/// Why a `Request` was abandoned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum AbandonReason {
+    /// Waited until its abandon tick without being handled.
+    Timeout,
+    /// Hung up before ever being enqueued, e.g. in an IVR menu.
+    PreQueue,
+}
+
+crate::id::id_counter!();
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Request {
     id: usize,
     required_attributes: Vec<Attribute>,
     start: Duration,
     abandon_ticks: Duration,
     handle_ticks: Duration,
+    enqueued: Option<Duration>,
     established: Option<Duration>,
     end: Option<Duration>,
+    served_by: Option<usize>,
+    clean_up: Option<Duration>,
+    abandon_reason: Option<AbandonReason>,
+    /// Whether this request waits for a callback rather than abandoning.
+    callback: bool,
+    /// The tick this request reached its abandon time and opted into a callback.
+    callback_tick: Option<Duration>,
+    /// Whether answering this request transfers it, spawning a follow-up request.
+    transfer: bool,
+    /// The id of the request this is a follow-up of.
+    transfer_of: Option<usize>,
+    /// The id of the follow-up request spawned when this was answered.
+    transferred_to: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_tags"))]
+    tags: Vec<&'static str>,
     status: Status,
     source: Client,
 }
 
+/// Tags are `&'static str`, so deserialized tags are leaked to give them a static lifetime. They
+/// are few and short, as a `RoutingStrategy` tags from a fixed set of names.
+#[cfg(feature = "serde")]
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<&'static str>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use alloc::{boxed::Box, string::String};
+    use serde::Deserialize;
+
+    let tags = Vec::<String>::deserialize(deserializer)?;
+    Ok(tags
+        .into_iter()
+        .map(|tag| &*Box::leak(tag.into_boxed_str()))
+        .collect())
+}
+
+impl Eq for Request {}
+
+/// Requests are ordered by `start`, with ties broken by `id` so that simultaneous arrivals have a
+/// reproducible order.
 impl Ord for Request {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.start.cmp(&other.start)
+        self.start
+            .cmp(&other.start)
+            .then_with(|| self.id.cmp(&other.id))
     }
 }
 
@@ -52,6 +103,7 @@ impl PartialOrd for Request {
 
 impl Request {
     /// Generate a `Request` based on the provided ticks and attributes
+    #[must_use]
     pub fn new(
         start: Duration,
         abandon_ticks: Duration,
@@ -60,13 +112,23 @@ impl Request {
         source: &Client,
     ) -> Self {
         Self {
-            id: ID_COUNTER.fetch_add(1, atomic::Ordering::SeqCst),
+            id: next_id(),
             start,
             abandon_ticks,
             handle_ticks,
             required_attributes,
+            enqueued: None,
             established: None,
             end: None,
+            served_by: None,
+            clean_up: None,
+            abandon_reason: None,
+            callback: false,
+            callback_tick: None,
+            transfer: false,
+            transfer_of: None,
+            transferred_to: None,
+            tags: Vec::new(),
             status: Status::default(),
             source: source.clone(),
         }
@@ -89,6 +151,75 @@ impl Request {
         self.start
     }
 
+    /// Returns the `ContactType` of the `Client` which made this request.
+    #[must_use]
+    #[inline]
+    pub(crate) fn channel(&self) -> ContactType {
+        self.source.channel
+    }
+
+    /// Returns the time this request takes to handle, before any `Server` efficiency.
+    #[must_use]
+    #[inline]
+    pub(crate) fn handle_ticks(&self) -> Duration {
+        self.handle_ticks
+    }
+
+    /// Returns the number of `Server`s required to handle this request.
+    #[must_use]
+    #[inline]
+    pub(crate) fn required_servers(&self) -> usize {
+        self.source.required_servers
+    }
+
+    /// Abandons at `start` without ever being enqueued, so it never waits.
+    pub(crate) fn abandon_before_queue(&mut self) {
+        self.status = Status::Abandoned;
+        self.abandon_reason = Some(AbandonReason::PreQueue);
+        self.end = Some(self.start);
+    }
+
+    /// Opts into a callback, so this request keeps waiting past its abandon time rather than
+    /// abandoning.
+    pub(crate) fn opt_into_callback(&mut self) {
+        self.callback = true;
+    }
+
+    /// Opts into a transfer, so answering this request spawns a follow-up request.
+    pub(crate) fn opt_into_transfer(&mut self) {
+        self.transfer = true;
+    }
+
+    /// Spawns the follow-up of an answered request which opted into a transfer, arriving when
+    /// handling ends. The follow-up has the same source and timings, and doesn't transfer again.
+    /// `None` if this request doesn't transfer, isn't answered, or already transferred.
+    pub(crate) fn transfer(&mut self) -> Option<Self> {
+        if !self.transfer || Status::Answered != self.status || self.transferred_to.is_some() {
+            return None;
+        }
+
+        let start = self.end?;
+        let abandon_ticks = start + self.abandon_ticks.saturating_sub(self.start);
+        let mut follow_up = Self::new(
+            start,
+            abandon_ticks,
+            self.handle_ticks,
+            self.required_attributes.clone(),
+            &self.source,
+        );
+        follow_up.transfer_of = Some(self.id);
+        self.transferred_to = Some(follow_up.id);
+
+        Some(follow_up)
+    }
+
+    /// Annotates this request with `tag`, unless it already has it.
+    pub(crate) fn tag(&mut self, tag: &'static str) {
+        if !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+    }
+
     #[allow(dead_code)]
     pub fn add_required_attribute(&mut self, attr: &Attribute) {
         self.required_attributes.push(attr.clone());
@@ -100,6 +231,13 @@ impl Request {
         &self.required_attributes
     }
 
+    /// The tick this request abandons at, unless it is handled first or waits for a callback.
+    #[must_use]
+    #[inline]
+    pub(crate) fn abandon_tick(&self) -> Duration {
+        self.abandon_ticks
+    }
+
     /// Returns the `Status` of this `Client`.
     #[must_use]
     #[inline]
@@ -119,6 +257,7 @@ impl Request {
             tick,
         );
         self.status = Status::Enqueued;
+        self.enqueued = Some(tick);
         //println!("[REQUEST] {} enqueued at {:?}", self.id, tick);
     }
 
@@ -139,22 +278,36 @@ impl Request {
             tick
         );
 
-        if self.abandon_ticks <= tick {
-            //println!("[REQUEST] {} abandoned at {:?}", self.id, tick);
-            self.status = Status::Abandoned;
-            self.end = Some(tick);
-            false
-        } else {
-            true
+        if self.abandon_ticks > tick {
+            return true;
         }
+
+        if self.callback {
+            self.callback_tick.get_or_insert(tick);
+            return true;
+        }
+
+        //println!("[REQUEST] {} abandoned at {:?}", self.id, tick);
+        self.status = Status::Abandoned;
+        self.abandon_reason = Some(AbandonReason::Timeout);
+        self.end = Some(tick);
+        false
     }
 
-    /// Mark request as handled at supplied `Duration`
+    /// The time `server` is busy with this request, handling and then cleaning up after it.
+    pub(crate) fn work_time(&self, server: &Server) -> Duration {
+        server.handle_time(self.handle_ticks, &self.required_attributes)
+            + server.clean_up_time(self.source.clean_up_time)
+    }
+
+    /// Mark request as handled by `server` at supplied `Duration`. Returns the tick the `server`
+    /// will be released, which is after both handling and cleaning up, and depends on the
+    /// `server`s efficiency for the required attributes and ACW factor.
     ///
     /// # Panics
     ///
     /// Will panic if trying to handle and request was not enqueued
-    pub(crate) fn handle(&mut self, tick: Duration) -> Duration {
+    pub(crate) fn handle(&mut self, tick: Duration, server: &Server) -> Duration {
         assert_eq!(
             Status::Enqueued,
             self.status,
@@ -170,38 +323,81 @@ impl Request {
 
         //println!("[REQUEST] {} handled at {:?}", self.id, tick);
         self.established = Some(tick);
-        let end = tick + self.handle_ticks;
+        let end = tick + server.handle_time(self.handle_ticks, &self.required_attributes);
         self.end = Some(end);
+        self.served_by = Some(server.id());
         self.status = Status::Answered;
 
-        end
+        let clean_up = server.clean_up_time(self.source.clean_up_time);
+        self.clean_up = Some(clean_up);
+
+        end + clean_up
     }
 
+    /// The time from `start` until the request stopped waiting. `established` takes precedence
+    /// over `end`, so an answered request waited until it was handled, and an abandoned request
+    /// (which is never established) waited until it abandoned. `None` whilst the request is
+    /// `Pending` or `Enqueued`, as it has not stopped waiting, or if it stopped before it started.
     #[must_use]
     fn wait_time(&self) -> Option<Duration> {
-        self.established.or(self.end).map(|t| t - self.start)
+        self.established
+            .or(self.end)
+            .and_then(|t| t.checked_sub(self.start))
     }
 
     #[must_use]
     #[allow(dead_code)]
     fn handle_time(&self) -> Option<Duration> {
-        if Status::Answered == self.status {
-            let established = self
-                .established
-                .expect("Client should have an established time if answered");
-            self.end.map(|t| t - established)
-        } else {
-            None
+        self.handle_time_until(Duration::MAX)
+    }
+
+    /// The handle time, only counting up until `horizon`. `None` unless answered, or if the
+    /// request ended before it was established.
+    #[must_use]
+    fn handle_time_until(&self, horizon: Duration) -> Option<Duration> {
+        if Status::Answered != self.status {
+            return None;
         }
+
+        let (established, end) = (self.established?, self.end?);
+        end.checked_sub(established)?;
+        Some(end.min(horizon).saturating_sub(established))
+    }
+
+    /// The clean up time, only counting up until `horizon`.
+    #[must_use]
+    fn clean_up_time_until(&self, horizon: Duration) -> Option<Duration> {
+        let end = self.end?;
+        self.clean_up
+            .map(|clean_up| clean_up.min(horizon.saturating_sub(end)))
     }
 
     #[must_use]
     pub fn data(&self) -> Data {
+        self.data_until(Duration::MAX)
+    }
+
+    /// The `Data` of this request, with any handling or cleaning up still in progress at
+    /// `horizon` clamped to it.
+    #[must_use]
+    pub fn data_until(&self, horizon: Duration) -> Data {
         Data {
             id: self.id,
+            start: self.start,
+            enqueued_at: self.enqueued,
+            // Like `wait_time`, an answered request stopped waiting when it was established
+            answered_or_abandoned_at: self.established.or(self.end),
             status: *self.status(),
             wait_time: self.wait_time(),
-            handle_time: self.handle_time(),
+            handle_time: self.handle_time_until(horizon),
+            clean_up_time: self.clean_up_time_until(horizon),
+            served_by: self.served_by,
+            abandon_reason: self.abandon_reason,
+            callback_time: self.callback_tick.and_then(|t| t.checked_sub(self.start)),
+            transfer_of: self.transfer_of,
+            transferred_to: self.transferred_to,
+            required_attributes: self.required_attributes.clone(),
+            tags: self.tags.clone(),
         }
     }
 }
@@ -209,6 +405,7 @@ impl Request {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use binary_heap_plus::BinaryHeap;
 
     const ABANDON_TICKS: Duration = Duration::new(1, 0);
     const HANDLE_TICKS: Duration = Duration::new(300, 0);
@@ -234,6 +431,20 @@ mod tests {
         (request, abandon_ticks)
     }
 
+    #[test]
+    fn ties_are_ordered_by_id() {
+        let (first, _) = default_request(START_TIME);
+        let (second, _) = default_request(START_TIME);
+        let (first_id, second_id) = (first.id(), second.id());
+
+        let mut heap = BinaryHeap::new_min();
+        heap.push(second);
+        heap.push(first);
+
+        assert_eq!(Some(first_id), heap.pop().map(|r| r.id()));
+        assert_eq!(Some(second_id), heap.pop().map(|r| r.id()));
+    }
+
     #[test]
     fn default_status_is_pending() {
         let (request, _) = default_request(Duration::ZERO);
@@ -250,6 +461,82 @@ mod tests {
         assert_eq!(&Status::Abandoned, request.status());
     }
 
+    #[test]
+    fn timed_out_records_timeout() {
+        let (mut request, abandon_tick) = enqueued_request(START_TIME);
+
+        request.tick_wait(abandon_tick - ONE_MS);
+        assert_eq!(None, request.data().abandon_reason);
+
+        request.tick_wait(abandon_tick);
+        assert_eq!(Some(AbandonReason::Timeout), request.data().abandon_reason);
+    }
+
+    #[test]
+    fn records_the_ticks_of_an_answered_request() {
+        let (mut request, _) = default_request(START_TIME);
+        let (enqueued, answered) = (START_TIME + ONE_MS, START_TIME * 2);
+
+        request.enqueue(enqueued);
+        assert_eq!(Some(enqueued), request.data().enqueued_at);
+        assert_eq!(None, request.data().answered_or_abandoned_at);
+
+        request.handle(answered, &Server::default());
+        let data = request.data();
+        assert_eq!(START_TIME, data.start);
+        assert_eq!(Some(enqueued), data.enqueued_at);
+        assert_eq!(Some(answered), data.answered_or_abandoned_at);
+    }
+
+    #[test]
+    fn records_the_ticks_of_an_abandoned_request() {
+        let (mut request, abandon_tick) = enqueued_request(START_TIME);
+        request.tick_wait(abandon_tick);
+
+        let data = request.data();
+        assert_eq!(START_TIME, data.start);
+        assert_eq!(Some(START_TIME), data.enqueued_at);
+        assert_eq!(Some(abandon_tick), data.answered_or_abandoned_at);
+
+        let (mut dropped, _) = default_request(START_TIME);
+        dropped.abandon_before_queue();
+        assert_eq!(None, dropped.data().enqueued_at);
+        assert_eq!(Some(START_TIME), dropped.data().answered_or_abandoned_at);
+    }
+
+    #[test]
+    fn callback_waits_past_abandonment_tick() {
+        let (mut request, abandon_tick) = enqueued_request(START_TIME);
+        request.opt_into_callback();
+
+        assert!(request.tick_wait(abandon_tick));
+        assert!(request.tick_wait(abandon_tick + ONE_MS));
+        assert_eq!(&Status::Enqueued, request.status());
+        assert_eq!(None, request.data().abandon_reason);
+
+        request.handle(abandon_tick + ONE_MS, &Server::default());
+        let data = request.data();
+        assert_eq!(Some(ABANDON_TICKS), data.callback_time);
+        assert_eq!(Some(ABANDON_TICKS + ONE_MS), data.wait_time);
+    }
+
+    #[test]
+    fn transfer_spawns_a_follow_up_when_handled() {
+        let (mut request, _) = enqueued_request(START_TIME);
+        request.opt_into_transfer();
+        assert!(request.transfer().is_none());
+
+        request.handle(START_TIME, &Server::default());
+        let follow_up = request
+            .transfer()
+            .expect("Answered transfer has a follow-up");
+
+        assert_eq!(START_TIME + HANDLE_TICKS, follow_up.start());
+        assert_eq!(Some(request.id()), follow_up.data().transfer_of);
+        assert_eq!(Some(follow_up.id()), request.data().transferred_to);
+        assert!(request.transfer().is_none());
+    }
+
     #[test]
     fn only_ticks_when_unanswered() {
         let (mut request, abandon_tick) = enqueued_request(START_TIME);
@@ -271,11 +558,65 @@ mod tests {
     fn handling_handles() {
         let (mut request, _) = enqueued_request(START_TIME);
 
-        assert_eq!(START_TIME + HANDLE_TICKS, request.handle(START_TIME));
+        assert_eq!(
+            START_TIME + HANDLE_TICKS,
+            request.handle(START_TIME, &Server::default())
+        );
         assert_eq!(&Status::Answered, request.status());
         assert_eq!(Some(Duration::ZERO), request.wait_time());
     }
 
+    #[test]
+    fn release_includes_clean_up_time() {
+        let client = Client {
+            clean_up_time: Duration::new(60, 0),
+            ..Client::default()
+        };
+        let enqueued = || {
+            let mut request =
+                Request::new(START_TIME, START_TIME, HANDLE_TICKS, Vec::new(), &client);
+            request.enqueue(START_TIME);
+            request
+        };
+        let trained = Server {
+            acw_factor: 0.5,
+            ..Server::default()
+        };
+
+        let mut request = enqueued();
+        assert_eq!(
+            START_TIME + HANDLE_TICKS + Duration::new(60, 0),
+            request.handle(START_TIME, &Server::default())
+        );
+        assert_eq!(Some(HANDLE_TICKS), request.handle_time());
+        assert_eq!(Some(Duration::new(60, 0)), request.data().clean_up_time);
+
+        assert_eq!(
+            START_TIME + HANDLE_TICKS + Duration::new(30, 0),
+            enqueued().handle(START_TIME, &trained)
+        );
+    }
+
+    #[test]
+    fn data_until_clamps_in_progress_handling() {
+        let client = Client {
+            clean_up_time: Duration::new(60, 0),
+            ..Client::default()
+        };
+        let mut request = Request::new(START_TIME, START_TIME, HANDLE_TICKS, Vec::new(), &client);
+        request.enqueue(START_TIME);
+        request.handle(START_TIME, &Server::default());
+
+        let horizon = START_TIME + Duration::new(100, 0);
+        let data = request.data_until(horizon);
+        assert_eq!(Some(Duration::new(100, 0)), data.handle_time);
+        assert_eq!(Some(Duration::ZERO), data.clean_up_time);
+
+        let data = request.data_until(START_TIME + HANDLE_TICKS + Duration::new(30, 0));
+        assert_eq!(Some(HANDLE_TICKS), data.handle_time);
+        assert_eq!(Some(Duration::new(30, 0)), data.clean_up_time);
+    }
+
     #[should_panic(expected = "Cannot tick Client when not enqueued")]
     #[test]
     fn handle_only_when_enqueued() {
@@ -283,7 +624,7 @@ mod tests {
 
         assert_eq!(&Status::Pending, request.status());
 
-        request.handle(START_TIME + (20 * ONE_MS));
+        request.handle(START_TIME + (20 * ONE_MS), &Server::default());
     }
 
     #[should_panic(expected = "Cannot tick in the past")]
@@ -291,7 +632,7 @@ mod tests {
     fn handling_only_works_in_future() {
         let (mut request, _) = enqueued_request(START_TIME);
 
-        request.handle(START_TIME - ONE_MS);
+        request.handle(START_TIME - ONE_MS, &Server::default());
     }
 
     #[test]
@@ -306,12 +647,40 @@ mod tests {
     #[test]
     fn wait_time_answered() {
         let (mut request, _) = enqueued_request(START_TIME);
-        request.handle(START_TIME * 2);
+        request.handle(START_TIME * 2, &Server::default());
 
         assert_eq!(&Status::Answered, request.status());
         assert_eq!(Some(START_TIME), request.wait_time());
     }
 
+    #[test]
+    fn wait_time_pending() {
+        let (request, _) = default_request(START_TIME);
+
+        assert_eq!(&Status::Pending, request.status());
+        assert_eq!(None, request.wait_time());
+        assert_eq!(None, request.data().wait_time);
+    }
+
+    #[test]
+    fn wait_time_answered_excludes_handle_time() {
+        let (mut request, _) = enqueued_request(START_TIME);
+        let end = request.handle(START_TIME + ONE_MS, &Server::default());
+
+        assert!(end > START_TIME + ONE_MS);
+        assert_eq!(Some(ONE_MS), request.wait_time());
+        assert_eq!(Some(ONE_MS), request.data().wait_time);
+    }
+
+    #[test]
+    fn wait_time_abandoned_is_until_abandoning() {
+        let (mut request, abandon_tick) = enqueued_request(START_TIME);
+        request.tick_wait(abandon_tick + ONE_MS);
+
+        assert_eq!(&Status::Abandoned, request.status());
+        assert_eq!(Some(ABANDON_TICKS + ONE_MS), request.data().wait_time);
+    }
+
     #[test]
     fn wait_time_unanswered() {
         let (mut request, _) = enqueued_request(START_TIME);
@@ -333,12 +702,93 @@ mod tests {
     #[test]
     fn handle_time_answered() {
         let (mut request, _) = enqueued_request(START_TIME);
-        request.handle(START_TIME * 2);
+        request.handle(START_TIME * 2, &Server::default());
 
         assert_eq!(&Status::Answered, request.status());
         assert_eq!(Some(HANDLE_TICKS), request.handle_time());
     }
 
+    #[test]
+    fn handle_time_efficient_server() {
+        let (mut request, _) = enqueued_request(START_TIME);
+        let server = Server {
+            efficiency: 0.5,
+            ..Server::default()
+        };
+
+        assert_eq!(
+            START_TIME + HANDLE_TICKS / 2,
+            request.handle(START_TIME, &server)
+        );
+        assert_eq!(Some(HANDLE_TICKS / 2), request.handle_time());
+    }
+
+    #[test]
+    fn handle_time_specialist_server() {
+        let client = Client::default();
+        let product = Attribute::new(1, None);
+        let request = || {
+            let mut request = Request::new(
+                START_TIME,
+                START_TIME + ABANDON_TICKS,
+                HANDLE_TICKS,
+                Vec::new(),
+                &client,
+            );
+            request.add_required_attribute(&product);
+            request.enqueue(START_TIME);
+            request
+        };
+
+        let generalist = Server {
+            attributes: vec![product.clone()],
+            ..Server::default()
+        };
+        let specialist = Server {
+            attributes: vec![product.clone()],
+            attribute_efficiency: vec![(product.id, 0.5)],
+            ..Server::default()
+        };
+        // Other products are handled at the default efficiency
+        let other_specialist = Server {
+            attribute_efficiency: vec![(2, 0.5)],
+            ..generalist.clone()
+        };
+
+        assert_eq!(
+            START_TIME + HANDLE_TICKS,
+            request().handle(START_TIME, &generalist)
+        );
+        assert_eq!(
+            START_TIME + HANDLE_TICKS / 2,
+            request().handle(START_TIME, &specialist)
+        );
+        assert_eq!(
+            START_TIME + HANDLE_TICKS,
+            request().handle(START_TIME, &other_specialist)
+        );
+    }
+
+    #[test]
+    fn inconsistent_times_are_none() {
+        let (mut request, _) = enqueued_request(START_TIME);
+        request.handle(START_TIME, &Server::default());
+
+        // Established before the request started
+        request.established = Some(START_TIME - ONE_MS);
+        assert_eq!(None, request.wait_time());
+
+        // Ended before the request was established
+        request.established = Some(START_TIME);
+        request.end = Some(START_TIME - ONE_MS);
+        assert_eq!(None, request.handle_time());
+
+        // Answered without ever being established
+        request.established = None;
+        assert_eq!(None, request.handle_time());
+        assert_eq!(None, request.data().wait_time);
+    }
+
     #[test]
     fn handle_time_abandonend() {
         let (mut request, abandon_tick) = enqueued_request(START_TIME);
@@ -2,16 +2,23 @@ use alloc::rc::Rc;
 use alloc::vec::Vec;
 use binary_heap_plus::{BinaryHeap, MinComparator};
 use core::cell::RefCell;
+use core::cmp::Reverse;
 use core::time::Duration;
 use hashbrown::HashMap;
 
 use super::{Request, Status};
+use crate::error::QueueError;
 use crate::routing::RequestData;
+use crate::Server;
 
 pub(crate) struct Queue {
     inner: Vec<Rc<RefCell<Request>>>,
     enqueued: BinaryHeap<Rc<RefCell<Request>>, MinComparator>,
     waiting: HashMap<usize, (Rc<RefCell<Request>>, RequestData)>,
+    // Requests which were answered or abandoned since they were last taken
+    completed: Vec<Rc<RefCell<Request>>>,
+    // The number of requests which have been answered or abandoned
+    resolved: usize,
 }
 
 impl Default for Queue {
@@ -20,21 +27,66 @@ impl Default for Queue {
             inner: Vec::new(),
             enqueued: BinaryHeap::new_min(),
             waiting: HashMap::new(),
+            completed: Vec::new(),
+            resolved: 0,
         }
     }
 }
 
 // Setup logic
 impl Queue {
+    /// Reserves capacity for at least `additional` more requests to be pushed.
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+
     pub fn push(&mut self, req: Request) {
         let req = Rc::new(RefCell::new(req));
 
         self.inner.push(req);
     }
 
-    // Assign all of the requests into the queue to be released
+    /// Rebuilds a queue of `requests`, e.g. from a `Checkpoint`. Pending requests are yet to be
+    /// released, and enqueued requests are waiting. Requests with an id in `completed` are yet to
+    /// be taken, in that order.
+    pub fn restore(requests: Vec<Request>, completed: &[usize]) -> Self {
+        let mut queue = Self::default();
+
+        for request in requests {
+            super::skip_ids_past(request.id());
+            let status = *request.status();
+            let request = Rc::new(RefCell::new(request));
+
+            match status {
+                Status::Pending => queue.enqueued.push(request.clone()),
+                Status::Enqueued => {
+                    let routing_data = RequestData::from(&*request.borrow());
+                    let id = request.borrow().id();
+                    queue.waiting.insert(id, (request.clone(), routing_data));
+                }
+                Status::Answered | Status::Abandoned => queue.resolved += 1,
+            }
+            queue.inner.push(request);
+        }
+
+        queue.completed = completed
+            .iter()
+            .filter_map(|id| queue.inner.iter().find(|r| r.borrow().id() == *id).cloned())
+            .collect();
+
+        queue
+    }
+
+    // Assign all of the requests into the queue to be released. Requests which abandoned before
+    // being queued are already complete.
     pub fn init(&mut self) {
+        self.enqueued.reserve(self.inner.len());
         for req in &self.inner {
+            if Status::Pending != *req.borrow().status() {
+                self.completed.push(req.clone());
+                self.resolved += 1;
+                continue;
+            }
             self.enqueued.push(req.clone());
         }
     }
@@ -51,9 +103,18 @@ impl Queue {
 
     fn tick_queued(&mut self, tick: Duration) {
         self.waiting.retain(|_, (request, _)| {
-            let mut request = request.borrow_mut();
-            request.tick_wait(tick);
-            &Status::Enqueued == request.status()
+            request.borrow_mut().tick_wait(tick);
+            let status = *request.borrow().status();
+            match status {
+                Status::Enqueued => true,
+                // Answered requests were removed when they were handled
+                Status::Abandoned => {
+                    self.completed.push(request.clone());
+                    self.resolved += 1;
+                    false
+                }
+                _ => false,
+            }
         });
     }
 
@@ -83,6 +144,17 @@ impl Queue {
     pub fn next_tick(&self) -> Option<Duration> {
         self.enqueued.peek().map(|c| c.borrow().start())
     }
+
+    /// The earliest tick after `tick` at which a waiting request abandons. Requests which are past
+    /// their abandon tick are waiting for a callback, so never abandon.
+    #[must_use]
+    pub fn next_abandon_tick(&self, tick: Duration) -> Option<Duration> {
+        self.waiting
+            .values()
+            .map(|(request, _)| request.borrow().abandon_tick())
+            .filter(|abandon_tick| *abandon_tick > tick)
+            .min()
+    }
 }
 
 // Misc
@@ -97,20 +169,193 @@ impl Queue {
         !self.waiting.is_empty()
     }
 
+    /// The number of requests currently waiting to be routed.
+    #[must_use]
+    pub fn waiting_count(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// The server time needed to handle every waiting request, before any `Server` efficiency.
+    #[must_use]
+    pub fn waiting_work(&self) -> Duration {
+        self.waiting
+            .values()
+            .map(|(request, _)| {
+                let request = request.borrow();
+                let servers = u32::try_from(request.required_servers()).unwrap_or(u32::MAX);
+                request.handle_ticks() * servers
+            })
+            .sum()
+    }
+
+    /// The waiting requests, ordered by id so routing is reproducible.
     #[must_use]
     pub fn routing_data(&self) -> Vec<&RequestData> {
-        self.waiting.values().map(|(_, r)| r).collect()
+        let mut data: Vec<&RequestData> = self.waiting.values().map(|(_, r)| r).collect();
+        data.sort_unstable_by_key(|r| r.id);
+        data
+    }
+
+    /// Returns the number of servers required by the waiting request with `id`.
+    #[must_use]
+    pub fn required_servers(&self, id: usize) -> Option<usize> {
+        self.waiting.get(&id).map(|(_, r)| r.required_servers)
+    }
+
+    /// Annotates the waiting request with `id` with `tag`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when the request with `id` is not waiting.
+    pub fn tag_request(&mut self, id: usize, tag: &'static str) -> Result<(), QueueError> {
+        let (request, _) = self.waiting.get(&id).ok_or(QueueError::NotWaiting(id))?;
+        request.borrow_mut().tag(tag);
+        Ok(())
+    }
+
+    /// Handles the waiting request with `id` by `servers`, returning the tick the `servers` are
+    /// released. Requests which were already handled are no longer waiting.
+    ///
+    /// A request handled by several servers takes as long as the slowest of them, which is the
+    /// server it is served by. Ties go to the lowest server id, so the order of `servers` doesn't
+    /// matter.
+    ///
+    /// # Errors
+    ///
+    /// Will error when the request with `id` is not waiting.
+    ///
+    /// # Panics
+    ///
+    /// Will panic when `servers` is empty.
+    pub fn handle_request(
+        &mut self,
+        id: usize,
+        tick: Duration,
+        servers: &[&Server],
+    ) -> Result<Duration, QueueError> {
+        let (request, _) = self.waiting.get(&id).ok_or(QueueError::NotWaiting(id))?;
+        let request = request.clone();
+
+        if &Status::Enqueued != request.borrow().status() {
+            return Err(QueueError::NotWaiting(id));
+        }
+        self.waiting.remove(&id);
+
+        let server = servers
+            .iter()
+            .copied()
+            .min_by_key(|server| (Reverse(request.borrow().work_time(server)), server.id()))
+            .expect("A request is handled by at least one server");
+        let release = request.borrow_mut().handle(tick, server);
+        let follow_up = request.borrow_mut().transfer();
+        self.completed.push(request);
+        self.resolved += 1;
+
+        // A transfer arrives as a new request once handling ends
+        if let Some(follow_up) = follow_up {
+            let follow_up = Rc::new(RefCell::new(follow_up));
+            self.inner.push(follow_up.clone());
+            self.enqueued.push(follow_up);
+        }
+
+        Ok(release)
+    }
+
+    /// The number of requests which have been answered or abandoned.
+    #[must_use]
+    pub fn resolved_count(&self) -> usize {
+        self.resolved
+    }
+
+    /// The ids of the requests which were answered or abandoned since they were last taken, in the
+    /// order they completed.
+    #[must_use]
+    pub fn completed_ids(&self) -> Vec<usize> {
+        self.completed.iter().map(|r| r.borrow().id()).collect()
+    }
+
+    /// Takes the requests which were answered or abandoned since this was last called, in the
+    /// order they completed.
+    pub fn take_completed(&mut self) -> Vec<Rc<RefCell<Request>>> {
+        core::mem::take(&mut self.completed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+
+    fn waiting_queue() -> (Queue, usize) {
+        let mut queue = Queue::default();
+        let request = Request::new(
+            Duration::ZERO,
+            Duration::from_secs(60),
+            Duration::from_secs(300),
+            Vec::new(),
+            &Client::default(),
+        );
+        let id = request.id();
+        queue.push(request);
+        queue.init();
+        queue.tick(Duration::ZERO);
+
+        (queue, id)
+    }
+
+    #[test]
+    fn reserves_for_pushed_requests() {
+        let mut queue = Queue::default();
+        queue.reserve(100);
+        let capacity = queue.inner.capacity();
+
+        for _ in 0..100 {
+            queue.push(Request::new(
+                Duration::ZERO,
+                Duration::from_secs(60),
+                Duration::from_secs(300),
+                Vec::new(),
+                &Client::default(),
+            ));
+        }
+        queue.init();
+
+        assert!(capacity >= 100);
+        assert_eq!(capacity, queue.inner.capacity());
+        assert_eq!(100, queue.enqueued.len());
+    }
+
+    #[test]
+    fn handles_waiting_request() {
+        let (mut queue, id) = waiting_queue();
+
+        assert_eq!(
+            Ok(Duration::from_secs(300)),
+            queue.handle_request(id, Duration::ZERO, &[&Server::default()])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_request() {
+        let (mut queue, id) = waiting_queue();
+        let unknown = id + 1000;
+
+        assert_eq!(
+            Err(QueueError::NotWaiting(unknown)),
+            queue.handle_request(unknown, Duration::ZERO, &[&Server::default()])
+        );
     }
 
-    pub fn handle_request(&mut self, id: usize, tick: Duration) -> Duration {
-        // TODO: This is not safe since id might not exist
-        let mut request = self
-            .waiting
-            .get(&id)
-            .expect("Client Id does not exist")
-            .0
-            .borrow_mut();
+    #[test]
+    fn rejects_handled_request() {
+        let (mut queue, id) = waiting_queue();
+        queue
+            .handle_request(id, Duration::ZERO, &[&Server::default()])
+            .unwrap();
 
-        request.handle(tick)
+        assert_eq!(
+            Err(QueueError::NotWaiting(id)),
+            queue.handle_request(id, Duration::ZERO, &[&Server::default()])
+        );
     }
 }
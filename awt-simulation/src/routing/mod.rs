@@ -1,23 +1,307 @@
+mod preferred_skill;
+mod primary_skill;
+mod random;
 mod request_data;
 mod server_data;
+mod weighted_fair;
+mod weighted_random;
 
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+use rand::RngCore;
 
-pub(crate) use request_data::RequestData;
-pub(crate) use server_data::ServerData;
+pub use preferred_skill::PreferredSkill;
+pub use primary_skill::PrimarySkill;
+pub use random::Random;
+pub use request_data::RequestData;
+pub use server_data::ServerData;
+pub use weighted_fair::WeightedFair;
+pub use weighted_random::{WeightedRandom, Weighting};
+
+/// Decides which waiting `Request`s are handled by which available `Server`s.
+///
+/// ```
+/// use awt_simulation::routing::RoutingStrategy;
+/// use awt_simulation::{attribute::Attribute, RequestData, ServerData};
+/// use core::time::Duration;
+/// use rand::RngCore;
+///
+/// /// Routes the longest waiting request to the first server with all of its attributes.
+/// struct Oldest;
+///
+/// impl RoutingStrategy for Oldest {
+///     fn route(
+///         &mut self,
+///         _tick: Duration,
+///         requests: Vec<&RequestData>,
+///         servers: Vec<&ServerData>,
+///         _rng: &mut dyn RngCore,
+///     ) -> Vec<(usize, usize)> {
+///         let Some(oldest) = requests.iter().min_by_key(|r| (r.start(), r.id())) else {
+///             return Vec::new();
+///         };
+///         servers
+///             .iter()
+///             .find(|s| Attribute::satisfies_all(s.attributes(), oldest.required_attributes()))
+///             .map(|s| vec![(oldest.id(), s.id())])
+///             .unwrap_or_default()
+///     }
+/// }
+///
+/// let skill = Attribute::new(1, None);
+/// let newer = RequestData::new(1, Duration::from_secs(5), Vec::new());
+/// let older = RequestData::new(2, Duration::from_secs(1), vec![skill.clone()]);
+/// let unskilled = ServerData::new(3, Vec::new());
+/// let skilled = ServerData::new(4, vec![skill]);
+///
+/// let routes = Oldest.route(
+///     Duration::from_secs(10),
+///     vec![&newer, &older],
+///     vec![&unskilled, &skilled],
+///     &mut rand::rngs::mock::StepRng::new(0, 1),
+/// );
+/// assert_eq!(vec![(2, 4)], routes);
+/// ```
+#[allow(clippy::module_name_repetitions)]
+pub trait RoutingStrategy {
+    /// Returns the `(request_id, server_id)` pairs to route at `tick`. Each server should be routed
+    /// at most once. A request is routed once for each of its `required_servers`, and is only
+    /// handled when all of them are routed. Any request which is not handled continues to wait.
+    fn route(
+        &mut self,
+        tick: Duration,
+        requests: Vec<&RequestData>,
+        servers: Vec<&ServerData>,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)>;
+
+    /// Returns `(request_id, tag)` pairs to annotate waiting requests with, e.g. `"escalated"`.
+    /// Called after each `route`, with tags applied before any request is handled, so a request
+    /// can be tagged as it is routed. Tags are reported in each request's `Data`. Default is none.
+    fn take_tags(&mut self) -> Vec<(usize, &'static str)> {
+        Vec::new()
+    }
+}
+
+/// Routes each request to the longest available server which can handle it. See `route_requests`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Greedy;
+
+impl RoutingStrategy for Greedy {
+    fn route(
+        &mut self,
+        _tick: Duration,
+        requests: Vec<&RequestData>,
+        servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        route_requests(requests, servers)
+    }
+}
+
+/// Routes the longest waiting requests first, each to the longest available server which can
+/// handle it. See `route_requests`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LongestWait;
+
+impl RoutingStrategy for LongestWait {
+    fn route(
+        &mut self,
+        _tick: Duration,
+        mut requests: Vec<&RequestData>,
+        servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        requests.sort_unstable_by_key(|request| (request.start, request.id));
+        route_requests(requests, servers)
+    }
+}
+
+/// Never routes any request, so every request waits until it abandons. A baseline to measure
+/// the value of other strategies against.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOp;
+
+impl RoutingStrategy for NoOp {
+    fn route(
+        &mut self,
+        _tick: Duration,
+        _requests: Vec<&RequestData>,
+        _servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+}
 
 // TODO: Support rlua? (allow custom lua scripts to execute and return routes)
-pub(crate) fn route_requests(
+/// The default routing. Each request is routed to the available servers which can handle its
+/// channel and satisfy its required attributes, preferring those which have been available the
+/// longest, then the lowest id. Requests are only routed when enough servers are available.
+///
+/// ```
+/// use awt_simulation::{attribute::Attribute, routing::route_requests, RequestData, ServerData};
+/// use core::time::Duration;
+///
+/// let request = RequestData::new(1, Duration::ZERO, vec![Attribute::new(1, None)]);
+/// let server = ServerData::new(2, vec![Attribute::new(1, None)]);
+///
+/// assert_eq!(vec![(1, 2)], route_requests(vec![&request], vec![&server]));
+/// ```
+#[must_use]
+pub fn route_requests(
     requests: Vec<&RequestData>,
     mut servers: Vec<&ServerData>,
 ) -> Vec<(usize, usize)> {
     let mut routes = Vec::new();
 
     for request in requests {
-        if let Some(server) = servers.pop() {
+        // Servers can only handle requests in the channels they support, with the attributes
+        // they require.
+        let mut eligible: Vec<usize> = servers
+            .iter()
+            .enumerate()
+            .filter(|(_, server)| server.can_handle(request))
+            .map(|(index, _)| index)
+            .collect();
+
+        if eligible.len() < request.required_servers {
+            continue;
+        }
+
+        // The servers which have been available the longest are routed first, so idle time is
+        // spread across the servers. Indexes are then in descending order so they remain valid
+        // whilst being removed.
+        eligible
+            .sort_unstable_by_key(|index| (servers[*index].available_since, servers[*index].id));
+        eligible.truncate(request.required_servers);
+        eligible.sort_unstable_by(|a, b| b.cmp(a));
+
+        for index in eligible {
+            let server = servers.swap_remove(index);
             routes.push((request.id, server.id));
         }
     }
 
     routes
 }
+
+/// Groups routes by request, in the order each request was first routed.
+pub(crate) fn group_routes(routes: Vec<(usize, usize)>) -> Vec<(usize, Vec<usize>)> {
+    let mut grouped: Vec<(usize, Vec<usize>)> = Vec::new();
+
+    for (request_id, server_id) in routes {
+        match grouped.iter_mut().find(|(id, _)| *id == request_id) {
+            Some((_, server_ids)) => server_ids.push(server_id),
+            None => grouped.push((request_id, vec![server_id])),
+        }
+    }
+
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Attribute, ContactType};
+    use core::time::Duration;
+
+    fn request(id: usize, channel: ContactType) -> RequestData {
+        RequestData {
+            id,
+            start: Duration::ZERO,
+            channel,
+            required_attributes: Vec::new(),
+            required_servers: 1,
+        }
+    }
+
+    fn server(id: usize, channels: &[ContactType]) -> ServerData {
+        ServerData {
+            id,
+            attributes: Vec::new(),
+            channels: channels.to_vec(),
+            secondary_attributes: Vec::new(),
+            available_since: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn routes_eligible_channel() {
+        let request = request(1, ContactType::Call);
+        let server = server(2, &[ContactType::Email, ContactType::Call]);
+
+        assert_eq!(vec![(1, 2)], route_requests(vec![&request], vec![&server]));
+    }
+
+    #[test]
+    fn does_not_route_ineligible_channel() {
+        let request = request(1, ContactType::Call);
+        let server = server(2, &[ContactType::Email]);
+
+        assert!(route_requests(vec![&request], vec![&server]).is_empty());
+    }
+
+    #[test]
+    fn routes_to_a_server_with_the_required_level() {
+        let request = RequestData::new(1, Duration::ZERO, vec![Attribute::new(1, Some(2))]);
+        let novice = ServerData::new(2, vec![Attribute::new(1, Some(1))]);
+        let expert = ServerData::new(3, vec![Attribute::new(1, Some(3))]);
+
+        assert!(route_requests(vec![&request], vec![&novice]).is_empty());
+        assert_eq!(
+            vec![(1, 3)],
+            route_requests(vec![&request], vec![&novice, &expert])
+        );
+    }
+
+    #[test]
+    fn routes_to_the_eligible_server() {
+        let call = request(1, ContactType::Call);
+        let email = request(2, ContactType::Email);
+        let call_server = server(3, &[ContactType::Call]);
+        let email_server = server(4, &[ContactType::Email]);
+
+        let mut routes = route_requests(vec![&call, &email], vec![&call_server, &email_server]);
+        routes.sort_unstable();
+
+        assert_eq!(vec![(1, 3), (2, 4)], routes);
+    }
+
+    #[test]
+    fn waits_for_all_required_servers() {
+        let request = RequestData {
+            required_servers: 2,
+            ..request(1, ContactType::Call)
+        };
+        let first = server(2, &[ContactType::Call]);
+        let second = server(3, &[ContactType::Call]);
+
+        assert!(route_requests(vec![&request], vec![&first]).is_empty());
+
+        let mut routes = route_requests(vec![&request], vec![&first, &second]);
+        routes.sort_unstable();
+        assert_eq!(vec![(1, 2), (1, 3)], routes);
+    }
+
+    #[test]
+    fn routes_to_the_longest_available_server() {
+        let request = request(1, ContactType::Call);
+        let recent = server(2, &[ContactType::Call]).with_available_since(Duration::from_secs(5));
+        let longest = server(3, &[ContactType::Call]).with_available_since(Duration::from_secs(1));
+        let tied = server(4, &[ContactType::Call]).with_available_since(Duration::from_secs(1));
+
+        assert_eq!(
+            vec![(1, 3)],
+            route_requests(vec![&request], vec![&recent, &tied, &longest])
+        );
+    }
+
+    #[test]
+    fn groups_routes_by_request() {
+        let routes = vec![(1, 2), (3, 4), (1, 5)];
+
+        assert_eq!(vec![(1, vec![2, 5]), (3, vec![4])], group_routes(routes));
+    }
+}
@@ -0,0 +1,172 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+use rand::RngCore;
+
+use super::{RequestData, RoutingStrategy, ServerData};
+use crate::Attribute;
+
+/// Routes requests only to servers with a preferred level of each required attribute, declining
+/// less skilled servers which could handle them. A request which has waited for `overflow_after`
+/// overflows, and may then be routed to any server which can handle it, preferred servers first.
+///
+/// The longest waiting request is routed first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferredSkill {
+    preferred_level: usize,
+    overflow_after: Duration,
+}
+
+impl PreferredSkill {
+    /// Prefer servers with at least `preferred_level` of each required attribute, or the required
+    /// level when that is higher.
+    #[must_use]
+    pub fn new(preferred_level: usize, overflow_after: Duration) -> Self {
+        Self {
+            preferred_level,
+            overflow_after,
+        }
+    }
+
+    fn is_preferred(&self, server: &ServerData, request: &RequestData) -> bool {
+        let preferred: Vec<Attribute> = request
+            .required_attributes
+            .iter()
+            .map(|required| {
+                let level = required.level.unwrap_or_default().max(self.preferred_level);
+                Attribute::new(required.id, Some(level))
+            })
+            .collect();
+
+        server.can_handle(request) && Attribute::satisfies_all(&server.attributes, &preferred)
+    }
+}
+
+impl RoutingStrategy for PreferredSkill {
+    fn route(
+        &mut self,
+        tick: Duration,
+        requests: Vec<&RequestData>,
+        servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        route_preferred(
+            tick,
+            requests,
+            servers,
+            self.overflow_after,
+            |server, request| self.is_preferred(server, request),
+        )
+    }
+}
+
+/// Routes the longest waiting request first, only to servers which `is_preferred` for it until it
+/// has waited for `overflow_after`. It may then be routed to any server which can handle it,
+/// preferred servers first.
+pub(super) fn route_preferred(
+    tick: Duration,
+    mut requests: Vec<&RequestData>,
+    mut servers: Vec<&ServerData>,
+    overflow_after: Duration,
+    is_preferred: impl Fn(&ServerData, &RequestData) -> bool,
+) -> Vec<(usize, usize)> {
+    let mut routes = Vec::new();
+
+    requests.sort_unstable_by_key(|request| (request.start, request.id));
+
+    for request in requests {
+        let overflowed = tick.saturating_sub(request.start) >= overflow_after;
+
+        let (preferred, others): (Vec<usize>, Vec<usize>) = (0..servers.len())
+            .filter(|index| servers[*index].can_handle(request))
+            .partition(|index| is_preferred(servers[*index], request));
+
+        let mut eligible = preferred;
+        if overflowed {
+            eligible.extend(others);
+        }
+        eligible.truncate(request.required_servers);
+
+        if eligible.len() < request.required_servers {
+            continue;
+        }
+
+        // Remove in descending order so the remaining indexes stay valid
+        eligible.sort_unstable();
+        for index in eligible.into_iter().rev() {
+            let server = servers.remove(index);
+            routes.push((request.id, server.id));
+        }
+    }
+
+    routes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::rngs::mock::StepRng;
+
+    const SPANISH: u64 = 1;
+    const OVERFLOW_AFTER: Duration = Duration::from_secs(60);
+
+    fn request(id: usize) -> RequestData {
+        RequestData::new(id, Duration::ZERO, vec![Attribute::new(SPANISH, Some(1))])
+    }
+
+    fn server(id: usize, level: usize) -> ServerData {
+        ServerData::new(id, vec![Attribute::new(SPANISH, Some(level))])
+    }
+
+    fn route(
+        tick: Duration,
+        requests: &[RequestData],
+        servers: &[ServerData],
+    ) -> Vec<(usize, usize)> {
+        let mut rng = StepRng::new(0, 1);
+        PreferredSkill::new(3, OVERFLOW_AFTER).route(
+            tick,
+            requests.iter().collect(),
+            servers.iter().collect(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn routes_to_preferred_server() {
+        let routes = route(
+            Duration::ZERO,
+            &[request(1)],
+            &[server(10, 1), server(11, 3)],
+        );
+
+        assert_eq!(vec![(1, 11)], routes);
+    }
+
+    #[test]
+    fn waits_for_preferred_server_before_overflowing() {
+        let (requests, servers) = ([request(1)], [server(10, 2)]);
+
+        assert!(route(Duration::ZERO, &requests, &servers).is_empty());
+        assert!(route(OVERFLOW_AFTER - Duration::from_secs(1), &requests, &servers).is_empty());
+        assert_eq!(vec![(1, 10)], route(OVERFLOW_AFTER, &requests, &servers));
+    }
+
+    #[test]
+    fn never_overflows_to_ineligible_server() {
+        let routes = route(OVERFLOW_AFTER, &[request(1)], &[server(10, 0)]);
+
+        assert!(routes.is_empty());
+    }
+
+    #[test]
+    fn overflowed_requests_still_prefer_preferred_servers() {
+        let routes = route(
+            OVERFLOW_AFTER,
+            &[request(1)],
+            &[server(10, 1), server(11, 4)],
+        );
+
+        assert_eq!(vec![(1, 11)], routes);
+    }
+}
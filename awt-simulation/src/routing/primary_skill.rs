@@ -0,0 +1,112 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+use rand::RngCore;
+
+use super::preferred_skill::route_preferred;
+use super::{RequestData, RoutingStrategy, ServerData};
+
+/// Routes requests only to servers which handle them with their primary skills, declining servers
+/// which would need one of their `secondary_attributes`. A request which has waited for
+/// `overflow_after` overflows, and may then be routed to a server with the secondary skill when
+/// no primary server is free.
+///
+/// The longest waiting request is routed first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrimarySkill {
+    overflow_after: Duration,
+}
+
+impl PrimarySkill {
+    #[must_use]
+    pub fn new(overflow_after: Duration) -> Self {
+        Self { overflow_after }
+    }
+}
+
+impl RoutingStrategy for PrimarySkill {
+    fn route(
+        &mut self,
+        tick: Duration,
+        requests: Vec<&RequestData>,
+        servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        route_preferred(
+            tick,
+            requests,
+            servers,
+            self.overflow_after,
+            ServerData::handles_with_primary,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Attribute;
+    use rand::rngs::mock::StepRng;
+
+    const BILLING: u64 = 1;
+    const SALES: u64 = 2;
+    const OVERFLOW_AFTER: Duration = Duration::from_secs(60);
+
+    fn request(id: usize, skill: u64) -> RequestData {
+        RequestData::new(id, Duration::ZERO, vec![Attribute::new(skill, None)])
+    }
+
+    /// A server with a primary `BILLING` skill, and a secondary `SALES` skill.
+    fn billing(id: usize) -> ServerData {
+        let skills = vec![Attribute::new(BILLING, None), Attribute::new(SALES, None)];
+        ServerData::new(id, skills).with_secondary_attributes(vec![SALES])
+    }
+
+    fn sales(id: usize) -> ServerData {
+        ServerData::new(id, vec![Attribute::new(SALES, None)])
+    }
+
+    fn route(
+        tick: Duration,
+        requests: &[RequestData],
+        servers: &[ServerData],
+    ) -> Vec<(usize, usize)> {
+        let mut rng = StepRng::new(0, 1);
+        PrimarySkill::new(OVERFLOW_AFTER).route(
+            tick,
+            requests.iter().collect(),
+            servers.iter().collect(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn routes_on_primary_skills() {
+        let servers = [billing(10)];
+
+        assert_eq!(
+            vec![(1, 10)],
+            route(Duration::ZERO, &[request(1, BILLING)], &servers)
+        );
+        assert!(route(Duration::ZERO, &[request(2, SALES)], &servers).is_empty());
+    }
+
+    #[test]
+    fn routes_on_secondary_skills_after_overflowing() {
+        let (requests, servers) = ([request(1, SALES)], [billing(10)]);
+
+        assert!(route(OVERFLOW_AFTER - Duration::from_secs(1), &requests, &servers).is_empty());
+        assert_eq!(vec![(1, 10)], route(OVERFLOW_AFTER, &requests, &servers));
+    }
+
+    #[test]
+    fn overflowed_requests_prefer_primary_servers() {
+        let routes = route(
+            OVERFLOW_AFTER,
+            &[request(1, SALES)],
+            &[billing(10), sales(11)],
+        );
+
+        assert_eq!(vec![(1, 11)], routes);
+    }
+}
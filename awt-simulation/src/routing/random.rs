@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+use rand::{seq::SliceRandom, RngCore};
+
+use super::{RequestData, RoutingStrategy, ServerData};
+
+/// Pairs requests with eligible servers uniformly at random. Requests are considered in a random
+/// order, so any waiting request may be routed first. Useful as a baseline for other strategies.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Random;
+
+impl RoutingStrategy for Random {
+    fn route(
+        &mut self,
+        _tick: Duration,
+        mut requests: Vec<&RequestData>,
+        mut servers: Vec<&ServerData>,
+        mut rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let mut routes = Vec::new();
+
+        // Queue order is not stable, so sort first to be reproducible with the same rng
+        requests.sort_unstable_by_key(|request| request.id);
+        servers.sort_unstable_by_key(|server| server.id);
+        requests.shuffle(&mut rng);
+        servers.shuffle(&mut rng);
+
+        for request in requests {
+            // Servers are shuffled, so the first eligible servers are a random selection
+            let eligible: Vec<usize> = servers
+                .iter()
+                .enumerate()
+                .filter(|(_, server)| server.can_handle(request))
+                .map(|(index, _)| index)
+                .take(request.required_servers)
+                .collect();
+
+            if eligible.len() < request.required_servers {
+                continue;
+            }
+
+            // Remove in descending order so the remaining indexes stay valid
+            for index in eligible.into_iter().rev() {
+                let server = servers.remove(index);
+                routes.push((request.id, server.id));
+            }
+        }
+
+        routes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ContactType;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    fn request(id: usize) -> RequestData {
+        RequestData {
+            id,
+            start: Duration::ZERO,
+            channel: ContactType::Call,
+            required_attributes: Vec::new(),
+            required_servers: 1,
+        }
+    }
+
+    fn server(id: usize) -> ServerData {
+        ServerData {
+            id,
+            attributes: Vec::new(),
+            channels: ContactType::ALL.to_vec(),
+            secondary_attributes: Vec::new(),
+            available_since: Duration::ZERO,
+        }
+    }
+
+    fn route(seed: u64) -> Vec<(usize, usize)> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let requests: Vec<RequestData> = (0..5).map(request).collect();
+        let servers: Vec<ServerData> = (10..13).map(server).collect();
+
+        Random.route(
+            Duration::ZERO,
+            requests.iter().collect(),
+            servers.iter().collect(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn reproducible_with_seed() {
+        let routes = route(7);
+
+        assert_eq!(3, routes.len());
+        assert_eq!(routes, route(7));
+    }
+
+    #[test]
+    fn servers_are_only_routed_once() {
+        let mut servers: Vec<usize> = route(3).iter().map(|(_, server)| *server).collect();
+        servers.sort_unstable();
+
+        assert_eq!(vec![10, 11, 12], servers);
+    }
+}
@@ -1,13 +1,61 @@
 use alloc::vec::Vec;
 use core::time::Duration;
 
-use crate::{Attribute, Request};
+use crate::{Attribute, ContactType, Request};
 
+/// A snapshot of a waiting `Request`, as presented to a `RoutingStrategy`.
 #[allow(dead_code)]
-pub(crate) struct RequestData {
-    pub id: usize,
-    pub start: Duration,
-    pub required_attributes: Vec<Attribute>,
+#[derive(Debug, Clone)]
+pub struct RequestData {
+    pub(crate) id: usize,
+    pub(crate) start: Duration,
+    pub(crate) channel: ContactType,
+    pub(crate) required_attributes: Vec<Attribute>,
+    pub(crate) required_servers: usize,
+}
+
+impl RequestData {
+    /// Creates a `Call` request which requires a single server.
+    #[must_use]
+    pub fn new(id: usize, start: Duration, required_attributes: Vec<Attribute>) -> Self {
+        Self {
+            id,
+            start,
+            channel: ContactType::default(),
+            required_attributes,
+            required_servers: 1,
+        }
+    }
+
+    #[must_use]
+    pub fn with_channel(mut self, channel: ContactType) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    #[must_use]
+    pub fn with_required_servers(mut self, required_servers: usize) -> Self {
+        self.required_servers = required_servers;
+        self
+    }
+
+    /// The id of the `Request`, to return in routes.
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The tick the `Request` arrived.
+    #[must_use]
+    pub fn start(&self) -> Duration {
+        self.start
+    }
+
+    /// The attributes a `Server` must have to handle the `Request`.
+    #[must_use]
+    pub fn required_attributes(&self) -> &[Attribute] {
+        &self.required_attributes
+    }
 }
 
 impl From<&Request> for RequestData {
@@ -15,7 +63,9 @@ impl From<&Request> for RequestData {
         Self {
             id: client.id(),
             start: client.start(),
+            channel: client.channel(),
             required_attributes: client.required_attributes().clone(),
+            required_servers: client.required_servers(),
         }
     }
 }
@@ -1,17 +1,101 @@
-use crate::{Attribute, Server};
+use super::RequestData;
+use crate::{Attribute, ContactType, QueueableServer};
 use alloc::vec::Vec;
+use core::time::Duration;
 
+/// A snapshot of an available `Server`, as presented to a `RoutingStrategy`.
 #[allow(dead_code)]
-pub(crate) struct ServerData {
-    pub id: usize,
-    pub attributes: Vec<Attribute>,
+#[derive(Debug, Clone)]
+pub struct ServerData {
+    pub(crate) id: usize,
+    pub(crate) attributes: Vec<Attribute>,
+    pub(crate) channels: Vec<ContactType>,
+    /// The ids of `attributes` which are secondary skills.
+    pub(crate) secondary_attributes: Vec<u64>,
+    /// The tick this `Server` became available for routing.
+    pub(crate) available_since: Duration,
 }
 
-impl From<&Server> for ServerData {
-    fn from(server: &Server) -> Self {
+impl ServerData {
+    /// Creates a server which handles all channels and has been available since tick zero.
+    #[must_use]
+    pub fn new(id: usize, attributes: Vec<Attribute>) -> Self {
+        Self {
+            id,
+            attributes,
+            channels: ContactType::ALL.to_vec(),
+            secondary_attributes: Vec::new(),
+            available_since: Duration::ZERO,
+        }
+    }
+
+    #[must_use]
+    pub fn with_channels(mut self, channels: Vec<ContactType>) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    #[must_use]
+    pub fn with_secondary_attributes(mut self, secondary_attributes: Vec<u64>) -> Self {
+        self.secondary_attributes = secondary_attributes;
+        self
+    }
+
+    #[must_use]
+    pub fn with_available_since(mut self, available_since: Duration) -> Self {
+        self.available_since = available_since;
+        self
+    }
+
+    /// The id of the `Server`, to return in routes.
+    #[must_use]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The attributes of the `Server`, e.g. skills.
+    #[must_use]
+    pub fn attributes(&self) -> &[Attribute] {
+        &self.attributes
+    }
+
+    /// The ids of the `attributes` which are secondary skills, e.g. for `PrimarySkill` routing.
+    #[must_use]
+    pub fn secondary_attributes(&self) -> &[u64] {
+        &self.secondary_attributes
+    }
+
+    /// Returns whether this server supports the `request`'s channel and satisfies all of its
+    /// required attributes, including their levels.
+    #[must_use]
+    pub fn can_handle(&self, request: &RequestData) -> bool {
+        self.channels.contains(&request.channel)
+            && Attribute::satisfies_all(&self.attributes, &request.required_attributes)
+    }
+
+    /// Returns whether this server can handle the `request` with its primary skills alone, i.e.
+    /// without any of its secondary attributes.
+    #[must_use]
+    pub fn handles_with_primary(&self, request: &RequestData) -> bool {
+        self.channels.contains(&request.channel)
+            && request.required_attributes.iter().all(|required| {
+                self.attributes.iter().any(|attribute| {
+                    !self.secondary_attributes.contains(&attribute.id)
+                        && attribute.satisfies(required)
+                })
+            })
+    }
+}
+
+impl From<&QueueableServer> for ServerData {
+    fn from(queueable: &QueueableServer) -> Self {
+        let server = queueable.server();
         Self {
             id: server.id(),
             attributes: server.attributes().clone(),
+            channels: server.channels().clone(),
+            secondary_attributes: server.secondary_attributes.clone(),
+            available_since: queueable.tick,
         }
     }
 }
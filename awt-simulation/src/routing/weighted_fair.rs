@@ -0,0 +1,266 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+use hashbrown::HashMap;
+use rand::RngCore;
+
+use super::{RequestData, RoutingStrategy, ServerData};
+
+/// Shares servers between groups of requests in proportion to each group's weight. A group is the
+/// set of attribute ids a request requires, so a group weighted `2.0` is routed roughly twice as
+/// often as a group weighted `1.0` when both are waiting. Groups without a weight are weighted
+/// `1.0`.
+///
+/// Within a group, the longest waiting request is routed first, to the servers which have been
+/// available the longest.
+#[derive(Debug, Clone, Default)]
+pub struct WeightedFair {
+    weights: HashMap<Vec<u64>, f64>,
+    /// The number of requests routed for each group.
+    routed: HashMap<Vec<u64>, usize>,
+}
+
+impl WeightedFair {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `weight` of the group requiring the attribute ids in `group`.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `weight` isn't positive and finite.
+    #[must_use]
+    pub fn with_weight(mut self, group: &[u64], weight: f64) -> Self {
+        assert!(
+            weight.is_finite() && weight > 0.0,
+            "Weight should be positive and finite. Received {weight}"
+        );
+        self.weights
+            .insert(group_key(group.iter().copied()), weight);
+        self
+    }
+
+    /// The share of servers the group has already received, relative to its weight.
+    #[allow(clippy::cast_precision_loss)]
+    fn share(&self, group: &Vec<u64>) -> f64 {
+        let routed = self.routed.get(group).copied().unwrap_or_default();
+        let weight = self.weights.get(group).copied().unwrap_or(1.0);
+
+        routed as f64 / weight
+    }
+}
+
+fn group_key(ids: impl Iterator<Item = u64>) -> Vec<u64> {
+    let mut key: Vec<u64> = ids.collect();
+    key.sort_unstable();
+    key.dedup();
+    key
+}
+
+fn group(request: &RequestData) -> Vec<u64> {
+    group_key(request.required_attributes.iter().map(|a| a.id))
+}
+
+impl RoutingStrategy for WeightedFair {
+    fn route(
+        &mut self,
+        _tick: Duration,
+        mut requests: Vec<&RequestData>,
+        mut servers: Vec<&ServerData>,
+        _rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let mut routes = Vec::new();
+
+        requests.sort_unstable_by_key(|request| (request.start, request.id));
+        servers.sort_unstable_by_key(|server| (server.available_since, server.id));
+
+        loop {
+            // Each request which enough servers could handle, paired with its group. Servers which
+            // can't handle a group never count toward its share.
+            let candidates: Vec<(usize, Vec<u64>)> = requests
+                .iter()
+                .enumerate()
+                .filter(|(_, request)| {
+                    servers
+                        .iter()
+                        .filter(|server| server.can_handle(request))
+                        .count()
+                        >= request.required_servers
+                })
+                .map(|(index, request)| (index, group(request)))
+                .collect();
+
+            // The oldest request of the group furthest behind its share. Candidates are in start
+            // order, so the first of each group is its oldest.
+            let Some((index, key)) =
+                candidates
+                    .into_iter()
+                    .fold(None, |best, candidate| match best {
+                        Some((_, ref key)) if self.share(key) <= self.share(&candidate.1) => best,
+                        _ => Some(candidate),
+                    })
+            else {
+                break;
+            };
+
+            let request = requests.remove(index);
+            for _ in 0..request.required_servers {
+                let position = servers
+                    .iter()
+                    .position(|server| server.can_handle(request))
+                    .expect("Request should have enough eligible servers");
+                let server = servers.remove(position);
+                routes.push((request.id, server.id));
+            }
+
+            *self.routed.entry(key).or_default() += 1;
+        }
+
+        routes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Attribute, ContactType};
+    use rand::rngs::mock::StepRng;
+
+    const SPANISH: u64 = 1;
+    const ENGLISH: u64 = 2;
+
+    fn request(id: usize, skill: u64) -> RequestData {
+        RequestData {
+            id,
+            start: Duration::from_secs(id as u64),
+            channel: ContactType::Call,
+            required_attributes: vec![Attribute::new(skill, None)],
+            required_servers: 1,
+        }
+    }
+
+    /// A server with the attributes of every `skills` group.
+    fn server(id: usize, skills: &[u64]) -> ServerData {
+        ServerData {
+            id,
+            attributes: skills
+                .iter()
+                .map(|skill| Attribute::new(*skill, None))
+                .collect(),
+            channels: ContactType::ALL.to_vec(),
+            secondary_attributes: Vec::new(),
+            available_since: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn shares_servers_by_weight() {
+        let mut rng = StepRng::new(0, 1);
+        let mut strategy = WeightedFair::new().with_weight(&[SPANISH], 2.0);
+
+        // Plenty of both groups are always waiting, but only a single server is available
+        let requests: Vec<RequestData> = (0..200)
+            .map(|id| request(id, if id % 2 == 0 { SPANISH } else { ENGLISH }))
+            .collect();
+        let mut waiting: Vec<&RequestData> = requests.iter().collect();
+        let server = server(1000, &[SPANISH, ENGLISH]);
+
+        let mut spanish = 0;
+        for tick in 0..90 {
+            let routes = strategy.route(
+                Duration::from_secs(tick),
+                waiting.clone(),
+                vec![&server],
+                &mut rng,
+            );
+            assert_eq!(1, routes.len());
+
+            let (request_id, _) = routes[0];
+            if request_id % 2 == 0 {
+                spanish += 1;
+            }
+            waiting.retain(|request| request.id != request_id);
+        }
+
+        assert_eq!(60, spanish);
+    }
+
+    #[test]
+    fn routes_oldest_request_of_a_group() {
+        let mut rng = StepRng::new(0, 1);
+        let mut strategy = WeightedFair::new();
+        let (newer, older) = (request(5, SPANISH), request(1, SPANISH));
+
+        let routes = strategy.route(
+            Duration::ZERO,
+            vec![&newer, &older],
+            vec![&server(1000, &[SPANISH])],
+            &mut rng,
+        );
+
+        assert_eq!(vec![(1, 1000)], routes);
+    }
+
+    #[test]
+    fn routes_every_group_without_contention() {
+        let mut rng = StepRng::new(0, 1);
+        let mut strategy = WeightedFair::new().with_weight(&[SPANISH], 2.0);
+        let (spanish, english) = (request(1, SPANISH), request(2, ENGLISH));
+
+        let routes = strategy.route(
+            Duration::ZERO,
+            vec![&spanish, &english],
+            vec![&server(1000, &[SPANISH]), &server(1001, &[ENGLISH])],
+            &mut rng,
+        );
+
+        assert_eq!(2, routes.len());
+    }
+
+    #[test]
+    fn routes_to_the_longest_available_server() {
+        let mut rng = StepRng::new(0, 1);
+        let mut strategy = WeightedFair::new();
+        let recent = server(1000, &[SPANISH]).with_available_since(Duration::from_secs(5));
+        let longest = server(1001, &[SPANISH]).with_available_since(Duration::from_secs(1));
+
+        let routes = strategy.route(
+            Duration::ZERO,
+            vec![&request(1, SPANISH)],
+            vec![&recent, &longest],
+            &mut rng,
+        );
+
+        assert_eq!(vec![(1, 1001)], routes);
+    }
+
+    #[test]
+    #[should_panic(expected = "Weight should be positive and finite")]
+    fn rejects_zero_weights() {
+        let _ = WeightedFair::new().with_weight(&[SPANISH], 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Weight should be positive and finite")]
+    fn rejects_negative_weights() {
+        let _ = WeightedFair::new().with_weight(&[SPANISH], -1.0);
+    }
+
+    #[test]
+    fn never_routes_to_an_unskilled_server() {
+        let mut rng = StepRng::new(0, 1);
+        let mut strategy = WeightedFair::new().with_weight(&[SPANISH], 2.0);
+        let (spanish, english) = (request(1, SPANISH), request(2, ENGLISH));
+
+        let routes = strategy.route(
+            Duration::ZERO,
+            vec![&spanish, &english],
+            vec![&server(1000, &[ENGLISH])],
+            &mut rng,
+        );
+
+        assert_eq!(vec![(2, 1000)], routes);
+    }
+}
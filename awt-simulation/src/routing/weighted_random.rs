@@ -0,0 +1,242 @@
+use alloc::vec::Vec;
+use core::time::Duration;
+use rand::{Rng, RngCore};
+
+use super::{RequestData, RoutingStrategy, ServerData};
+
+/// The key used to weight the random selection of a `Server`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weighting {
+    /// Weighted by the sum of the server's levels in the attributes the request requires, plus 1.
+    SkillLevel,
+    /// Weighted by how long the server has been available, spreading load to idle servers.
+    IdleTime,
+}
+
+/// Routes each request to a random server which can handle it, weighted by `Weighting` among only
+/// those eligible servers.
+#[derive(Debug, Clone, Copy)]
+pub struct WeightedRandom {
+    weighting: Weighting,
+}
+
+impl WeightedRandom {
+    #[must_use]
+    pub fn new(weighting: Weighting) -> Self {
+        Self { weighting }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn weight(self, tick: Duration, request: &RequestData, server: &ServerData) -> f64 {
+        match self.weighting {
+            Weighting::SkillLevel => {
+                let level: usize = server
+                    .attributes
+                    .iter()
+                    .filter(|attribute| {
+                        request
+                            .required_attributes
+                            .iter()
+                            .any(|required| required.id == attribute.id)
+                    })
+                    .filter_map(|attribute| attribute.level)
+                    .sum();
+                (level + 1) as f64
+            }
+            Weighting::IdleTime => {
+                // Add a nanosecond so servers which just became available can still be selected
+                (tick.saturating_sub(server.available_since) + Duration::new(0, 1)).as_secs_f64()
+            }
+        }
+    }
+}
+
+impl RoutingStrategy for WeightedRandom {
+    fn route(
+        &mut self,
+        tick: Duration,
+        requests: Vec<&RequestData>,
+        mut servers: Vec<&ServerData>,
+        rng: &mut dyn RngCore,
+    ) -> Vec<(usize, usize)> {
+        let mut routes = Vec::new();
+
+        for request in requests {
+            let mut candidates: Vec<(usize, f64)> = servers
+                .iter()
+                .enumerate()
+                .filter(|(_, server)| server.can_handle(request))
+                .map(|(index, server)| (index, self.weight(tick, request, server)))
+                .collect();
+
+            if candidates.len() < request.required_servers {
+                continue;
+            }
+
+            let mut selected = Vec::new();
+            for _ in 0..request.required_servers {
+                let total: f64 = candidates.iter().map(|(_, weight)| weight).sum();
+                let mut roll = rng.gen_range(0f64..total);
+                let position = candidates
+                    .iter()
+                    .position(|(_, weight)| {
+                        roll -= weight;
+                        roll < 0f64
+                    })
+                    .unwrap_or(candidates.len() - 1);
+
+                selected.push(candidates.swap_remove(position).0);
+            }
+
+            // Remove in descending order so the remaining indexes stay valid
+            selected.sort_unstable_by(|a, b| b.cmp(a));
+            for index in selected {
+                let server = servers.swap_remove(index);
+                routes.push((request.id, server.id));
+            }
+        }
+
+        routes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Attribute, ContactType};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    const SKILL: u64 = 1;
+
+    fn request(id: usize) -> RequestData {
+        RequestData {
+            id,
+            start: Duration::ZERO,
+            channel: ContactType::Call,
+            required_attributes: vec![Attribute::new(SKILL, Some(1))],
+            required_servers: 1,
+        }
+    }
+
+    fn server(id: usize, level: usize, available_since: Duration) -> ServerData {
+        ServerData {
+            id,
+            attributes: vec![Attribute::new(SKILL, Some(level))],
+            channels: ContactType::ALL.to_vec(),
+            secondary_attributes: Vec::new(),
+            available_since,
+        }
+    }
+
+    fn route(weighting: Weighting, seed: u64) -> Vec<(usize, usize)> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let requests = [request(1), request(2)];
+        let servers = [
+            server(10, 1, Duration::ZERO),
+            server(11, 5, Duration::ZERO),
+            server(12, 9, Duration::ZERO),
+        ];
+
+        WeightedRandom::new(weighting).route(
+            Duration::from_secs(10),
+            requests.iter().collect(),
+            servers.iter().collect(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn reproducible_with_seed() {
+        for weighting in [Weighting::SkillLevel, Weighting::IdleTime] {
+            let routes = route(weighting, 7);
+
+            assert_eq!(2, routes.len());
+            assert_eq!(routes, route(weighting, 7));
+        }
+    }
+
+    #[test]
+    fn servers_are_only_routed_once() {
+        let routes = route(Weighting::SkillLevel, 3);
+
+        assert_ne!(routes[0].1, routes[1].1);
+    }
+
+    #[test]
+    fn never_routes_to_an_unskilled_server() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let request = request(1);
+        let unskilled = ServerData::new(10, Vec::new());
+        let skilled = server(11, 1, Duration::ZERO);
+
+        for weighting in [Weighting::SkillLevel, Weighting::IdleTime] {
+            let mut strategy = WeightedRandom::new(weighting);
+            for _ in 0..100 {
+                let routes = strategy.route(
+                    Duration::from_secs(10),
+                    vec![&request],
+                    vec![&unskilled, &skilled],
+                    &mut rng,
+                );
+                assert_eq!(vec![(1, 11)], routes);
+            }
+            let routes = strategy.route(
+                Duration::from_secs(10),
+                vec![&request],
+                vec![&unskilled],
+                &mut rng,
+            );
+            assert!(routes.is_empty());
+        }
+    }
+
+    #[test]
+    fn skill_level_favours_skilled_servers() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let request = request(1);
+        let servers = [server(10, 1, Duration::ZERO), server(11, 9, Duration::ZERO)];
+        let mut strategy = WeightedRandom::new(Weighting::SkillLevel);
+
+        let skilled = (0..1000)
+            .filter(|_| {
+                let routes = strategy.route(
+                    Duration::ZERO,
+                    vec![&request],
+                    servers.iter().collect(),
+                    &mut rng,
+                );
+                routes[0].1 == 11
+            })
+            .count();
+
+        // Expected 10/12 of the time
+        assert!(skilled > 780, "skilled server routed {skilled} times");
+    }
+
+    #[test]
+    fn idle_time_favours_idle_servers() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let request = request(1);
+        let servers = [
+            server(10, 1, Duration::from_secs(9)),
+            server(11, 1, Duration::ZERO),
+        ];
+        let mut strategy = WeightedRandom::new(Weighting::IdleTime);
+
+        let idle = (0..1000)
+            .filter(|_| {
+                let routes = strategy.route(
+                    Duration::from_secs(10),
+                    vec![&request],
+                    servers.iter().collect(),
+                    &mut rng,
+                );
+                routes[0].1 == 11
+            })
+            .count();
+
+        // Expected 10/11 of the time
+        assert!(idle > 850, "idle server routed {idle} times");
+    }
+}
@@ -1,25 +1,53 @@
-use super::Attribute;
+use super::{Attribute, ContactType};
 use alloc::vec::Vec;
 use core::cmp::Ordering;
-use core::sync::{atomic, atomic::AtomicUsize};
 use core::time::Duration;
 
 pub(crate) mod queue;
+pub mod stats;
 
-static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+pub use stats::Stats;
+
+crate::id::id_counter!();
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Server {
     pub id: usize,
     pub attributes: Vec<Attribute>,
+    /// The `ContactType`s this `Server` is able to handle.
+    pub channels: Vec<ContactType>,
+    /// Multiplier applied to the handle time of any `Request` this `Server` handles. e.g. `0.9`
+    /// handles requests 10% faster. Must be positive.
+    pub efficiency: f64,
+    /// `(attribute id, efficiency)` overrides of `efficiency` for requests which require that
+    /// attribute, e.g. a specialist which handles requests requiring their product in half the
+    /// time. When several required attributes have an override, the slowest is used.
+    pub attribute_efficiency: Vec<(u64, f64)>,
+    /// Multiplier applied to the clean up time (ACW) of any `Request` this `Server` handles. e.g.
+    /// `0.5` wraps up in half the time. Must be positive.
+    pub acw_factor: f64,
+    /// The fraction of routing opportunities (`0.0..=1.0`) this `Server` is present for whilst
+    /// waiting, e.g. `0.5` for a server shared with other work half of the time. Each tick's
+    /// presence is drawn from the `Simulation` rng.
+    pub availability: f64,
+    /// The ids of `attributes` which are secondary skills, handled only when a request overflows
+    /// with `PrimarySkill` routing. Every other attribute is a primary skill.
+    pub secondary_attributes: Vec<u64>,
 }
 
 impl Default for Server {
     fn default() -> Self {
         Self {
-            id: ID_COUNTER.fetch_add(1, atomic::Ordering::SeqCst),
+            id: next_id(),
             attributes: Vec::new(),
+            channels: ContactType::ALL.to_vec(),
+            efficiency: 1.0,
+            attribute_efficiency: Vec::new(),
+            acw_factor: 1.0,
+            availability: 1.0,
+            secondary_attributes: Vec::new(),
         }
     }
 }
@@ -34,18 +62,75 @@ impl Server {
     pub fn attributes(&self) -> &Vec<Attribute> {
         &self.attributes
     }
+
+    #[must_use]
+    pub fn channels(&self) -> &Vec<ContactType> {
+        &self.channels
+    }
+
+    /// Returns the time this `Server` takes to handle a request with the provided `handle_ticks`
+    /// and `required_attributes`.
+    #[must_use]
+    pub fn handle_time(
+        &self,
+        handle_ticks: Duration,
+        required_attributes: &[Attribute],
+    ) -> Duration {
+        handle_ticks.mul_f64(self.efficiency_for(required_attributes))
+    }
+
+    /// The efficiency this `Server` handles a request with `required_attributes` at. The slowest
+    /// `attribute_efficiency` override of the required attributes, otherwise `efficiency`.
+    #[must_use]
+    pub fn efficiency_for(&self, required_attributes: &[Attribute]) -> f64 {
+        self.attribute_efficiency
+            .iter()
+            .filter(|(id, _)| required_attributes.iter().any(|a| a.id == *id))
+            .map(|(_, efficiency)| *efficiency)
+            .reduce(f64::max)
+            .unwrap_or(self.efficiency)
+    }
+
+    /// Returns the time this `Server` takes to clean up after a request with the provided
+    /// `clean_up_ticks`.
+    #[must_use]
+    pub fn clean_up_time(&self, clean_up_ticks: Duration) -> Duration {
+        clean_up_ticks.mul_f64(self.acw_factor)
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QueueableServer {
     server: Server,
     pub tick: Duration,
+    /// The total time this `Server` has been assigned to requests, including any time past the
+    /// end of the simulation.
+    pub busy: Duration,
+    /// The tick this `Server` joined the `Simulation`.
+    pub added: Duration,
+    /// The tick this `Server` left the `Simulation`. It is not routed to from then on.
+    pub removed: Option<Duration>,
+    /// The number of requests this `Server` has been assigned.
+    pub handled: usize,
+}
+
+impl PartialEq for QueueableServer {
+    fn eq(&self, other: &Self) -> bool {
+        self.tick == other.tick && self.server.id == other.server.id
+    }
 }
 
+impl Eq for QueueableServer {}
+
+/// Servers are ordered by `tick`, with ties broken by the `Server` id so that simultaneous releases
+/// have a reproducible order.
 impl Ord for QueueableServer {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.tick.cmp(&other.tick)
+        self.tick
+            .cmp(&other.tick)
+            .then_with(|| self.server.id.cmp(&other.server.id))
     }
 }
 
@@ -61,6 +146,10 @@ impl QueueableServer {
         Self {
             server,
             tick: Duration::ZERO,
+            busy: Duration::ZERO,
+            added: Duration::ZERO,
+            removed: None,
+            handled: 0,
         }
     }
 
@@ -69,3 +158,23 @@ impl QueueableServer {
         &self.server
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binary_heap_plus::BinaryHeap;
+
+    #[test]
+    fn ties_are_ordered_by_id() {
+        let first = QueueableServer::new(Server::default());
+        let second = QueueableServer::new(Server::default());
+        let (first_id, second_id) = (first.server().id(), second.server().id());
+
+        let mut heap = BinaryHeap::new_min();
+        heap.push(second);
+        heap.push(first);
+
+        assert_eq!(Some(first_id), heap.pop().map(|s| s.server().id()));
+        assert_eq!(Some(second_id), heap.pop().map(|s| s.server().id()));
+    }
+}
@@ -3,10 +3,24 @@ use binary_heap_plus::{BinaryHeap, MinComparator};
 use core::cell::RefCell;
 use core::time::Duration;
 use hashbrown::HashMap;
+use rand::{Rng, RngCore};
 
-use super::QueueableServer;
+use super::{QueueableServer, Stats};
+use crate::error::QueueError;
 use crate::routing::ServerData;
 
+/// Which part of the `Queue` a server is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum Queued {
+    /// Available to be routed to.
+    Waiting,
+    /// Busy until its tick.
+    Enqueued,
+    /// Neither, as it was removed or is yet to be initialised.
+    Neither,
+}
+
 pub(crate) struct Queue {
     inner: Vec<Rc<RefCell<QueueableServer>>>,
     enqueued: BinaryHeap<Rc<RefCell<QueueableServer>>, MinComparator>,
@@ -31,9 +45,74 @@ impl Queue {
         self.inner.push(server);
     }
 
+    /// Adds a server to a running simulation at `tick`, available for routing immediately.
+    pub fn push_live(&mut self, server: QueueableServer, tick: Duration) {
+        let server = Rc::new(RefCell::new(server));
+        {
+            let mut queueable = server.borrow_mut();
+            queueable.added = tick;
+            queueable.tick = tick;
+        }
+
+        let routing_data = ServerData::from(&*server.borrow());
+        self.waiting.insert(
+            server.borrow().server().id(),
+            (server.clone(), routing_data),
+        );
+        self.inner.push(server);
+    }
+
+    /// Rebuilds a queue of `servers`, e.g. from a `Checkpoint`, with each in the part of the queue
+    /// it was in.
+    pub fn restore(servers: Vec<(QueueableServer, Queued)>) -> Self {
+        let mut queue = Self::default();
+
+        for (server, queued) in servers {
+            super::skip_ids_past(server.server().id());
+            let server = Rc::new(RefCell::new(server));
+
+            match queued {
+                Queued::Waiting => {
+                    let routing_data = ServerData::from(&*server.borrow());
+                    let id = server.borrow().server().id();
+                    queue.waiting.insert(id, (server.clone(), routing_data));
+                }
+                Queued::Enqueued => queue.enqueued.push(server.clone()),
+                Queued::Neither => (),
+            }
+            queue.inner.push(server);
+        }
+
+        queue
+    }
+
+    /// A copy of every server, along with the part of the queue it is in.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<(QueueableServer, Queued)> {
+        self.inner
+            .iter()
+            .map(|server| {
+                let id = server.borrow().server().id();
+                let queued = if self.waiting.contains_key(&id) {
+                    Queued::Waiting
+                } else if self.enqueued.iter().any(|s| Rc::ptr_eq(s, server)) {
+                    Queued::Enqueued
+                } else {
+                    Queued::Neither
+                };
+                (server.borrow().clone(), queued)
+            })
+            .collect()
+    }
+
     pub fn init(&mut self) {
+        self.waiting.reserve(self.inner.len());
         for server in &self.inner {
-            let routing_data = ServerData::from(server.borrow().server());
+            if server.borrow().removed.is_some() {
+                continue;
+            }
+
+            let routing_data = ServerData::from(&*server.borrow());
             self.waiting.insert(
                 server.borrow().server().id(),
                 (server.clone(), routing_data),
@@ -56,7 +135,11 @@ impl Queue {
                 .pop()
                 .expect("Server was peeked and should have popped");
 
-            let routing_data = ServerData::from(next_server.borrow().server());
+            if next_server.borrow().removed.is_some() {
+                continue;
+            }
+
+            let routing_data = ServerData::from(&*next_server.borrow());
 
             self.waiting.insert(
                 next_server.borrow().server().id(),
@@ -69,24 +152,186 @@ impl Queue {
     pub fn next_tick(&self) -> Option<Duration> {
         self.enqueued.peek().map(|c| c.borrow().tick)
     }
+
+    /// Whether no server is waiting or will be released, i.e. nothing can be routed until a
+    /// server is added.
+    #[must_use]
+    pub fn is_unstaffed(&self) -> bool {
+        self.waiting.is_empty()
+            && self
+                .enqueued
+                .iter()
+                .all(|server| server.borrow().removed.is_some())
+    }
 }
 
 // Misc
 impl Queue {
+    /// The waiting servers which are present to be routed to, ordered by id so routing is
+    /// reproducible. Servers with an `availability` below 1.0 are only present with that
+    /// probability, drawn from `rng` in id order.
     #[must_use]
-    pub fn routing_data(&self) -> Vec<&ServerData> {
-        self.waiting.values().map(|(_, s)| s).collect()
+    pub fn routing_data(&self, rng: &mut dyn RngCore) -> Vec<&ServerData> {
+        let mut waiting: Vec<_> = self.waiting.values().collect();
+        waiting.sort_unstable_by_key(|(_, s)| s.id);
+        waiting
+            .into_iter()
+            .filter(|(server, _)| {
+                let availability = server.borrow().server().availability;
+                availability >= 1.0 || rng.gen_bool(availability.max(0.0))
+            })
+            .map(|(_, s)| s)
+            .collect()
     }
 
-    pub fn enqueue(&mut self, id: usize, until: Duration) {
-        // TODO: Safely chceck that the server_queue has this server_id
-        let (server, _) = self
-            .waiting
-            .remove(&id)
-            .expect("Server Id should have been queued");
+    /// The number of servers currently handling requests.
+    #[must_use]
+    pub fn busy_count(&self) -> usize {
+        self.enqueued.len()
+    }
 
-        server.borrow_mut().tick = until;
+    /// The time until every busy server is released from `tick`, including any clean up.
+    #[must_use]
+    pub fn remaining_work(&self, tick: Duration) -> Duration {
+        self.enqueued
+            .iter()
+            .map(|server| server.borrow().tick.saturating_sub(tick))
+            .sum()
+    }
+
+    #[must_use]
+    pub fn waiting_server(&self, id: usize) -> Option<Rc<RefCell<QueueableServer>>> {
+        self.waiting.get(&id).map(|(server, _)| server.clone())
+    }
+
+    /// Returns the `Stats` of each server, for a simulation from `start` until `end`.
+    #[must_use]
+    pub fn stats(&self, start: Duration, end: Duration) -> Vec<Stats> {
+        self.inner
+            .iter()
+            .map(|server| {
+                let server = server.borrow();
+                Stats {
+                    id: server.server().id(),
+                    // Requests handled at the end of the simulation may run past it
+                    busy: server.busy - server.tick.saturating_sub(end),
+                    available: server
+                        .removed
+                        .map_or(end, |removed| removed.min(end))
+                        .saturating_sub(start.max(server.added)),
+                }
+            })
+            .collect()
+    }
+
+    /// The `(server id, handled count)` of each server, in the order they were added.
+    #[must_use]
+    pub fn call_counts(&self) -> Vec<(usize, usize)> {
+        self.inner
+            .iter()
+            .map(|server| {
+                let server = server.borrow();
+                (server.server().id(), server.handled)
+            })
+            .collect()
+    }
+
+    /// Removes the server with `id` from the `Simulation` at `tick`. A waiting server is removed
+    /// immediately, whilst a busy server is removed once it is released. Returns false when there
+    /// is no such server, or it was already removed.
+    pub fn remove(&mut self, id: usize, tick: Duration) -> bool {
+        if let Some((server, _)) = self.waiting.remove(&id) {
+            server.borrow_mut().removed = Some(tick);
+            return true;
+        }
+
+        let Some(server) = self.inner.iter().find(|s| s.borrow().server().id() == id) else {
+            return false;
+        };
+
+        let mut server = server.borrow_mut();
+        if server.removed.is_some() {
+            return false;
+        }
+        server.removed = Some(server.tick.max(tick));
+
+        true
+    }
+
+    /// Removes the server with `id` from routing until the `until` tick. The server is counted as
+    /// busy from `tick`.
+    ///
+    /// # Errors
+    ///
+    /// Will error when the server with `id` is not waiting.
+    pub fn enqueue(
+        &mut self,
+        id: usize,
+        tick: Duration,
+        until: Duration,
+    ) -> Result<(), QueueError> {
+        let (server, _) = self.waiting.remove(&id).ok_or(QueueError::NotWaiting(id))?;
+
+        let mut queueable = server.borrow_mut();
+        queueable.busy += until - tick;
+        queueable.tick = until;
+        queueable.handled += 1;
+        drop(queueable);
 
         self.enqueued.push(server);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Server;
+
+    fn waiting_queue() -> (Queue, usize) {
+        let mut queue = Queue::default();
+        let server = Server::default();
+        let id = server.id();
+        queue.push(QueueableServer::new(server));
+        queue.init();
+
+        (queue, id)
+    }
+
+    #[test]
+    fn enqueues_waiting_server() {
+        let (mut queue, id) = waiting_queue();
+
+        assert_eq!(
+            Ok(()),
+            queue.enqueue(id, Duration::ZERO, Duration::from_secs(300))
+        );
+        assert_eq!(1, queue.busy_count());
+    }
+
+    #[test]
+    fn rejects_unknown_server() {
+        let (mut queue, id) = waiting_queue();
+        let unknown = id + 1000;
+
+        assert_eq!(
+            Err(QueueError::NotWaiting(unknown)),
+            queue.enqueue(unknown, Duration::ZERO, Duration::from_secs(300))
+        );
+        assert_eq!(0, queue.busy_count());
+    }
+
+    #[test]
+    fn rejects_busy_server() {
+        let (mut queue, id) = waiting_queue();
+        queue
+            .enqueue(id, Duration::ZERO, Duration::from_secs(300))
+            .unwrap();
+
+        assert_eq!(
+            Err(QueueError::NotWaiting(id)),
+            queue.enqueue(id, Duration::ZERO, Duration::from_secs(300))
+        );
     }
 }
@@ -0,0 +1,19 @@
+use core::time::Duration;
+
+/// Per `Server` accounting of a `Simulation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub id: usize,
+    /// The time spent handling and cleaning up after requests.
+    pub busy: Duration,
+    /// The time the `Server` was available to the `Simulation`.
+    pub available: Duration,
+}
+
+impl Stats {
+    /// The time spent not handling requests.
+    #[must_use]
+    pub fn idle(&self) -> Duration {
+        self.available.saturating_sub(self.busy)
+    }
+}
@@ -0,0 +1,15 @@
+/// How a `Simulation` advances between ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TickMode {
+    /// Steps by `tick_size` whilst requests are waiting, otherwise jumps directly to the next
+    /// request or server release. Steps stop early at the tick a waiting request abandons, so
+    /// abandons are exact whatever the `tick_size`. Idle periods cost nothing, so this is much
+    /// faster for sparse simulations. Without any servers, waiting requests can only abandon, so
+    /// it jumps between the ticks they abandon at instead of stepping.
+    #[default]
+    EventDriven,
+    /// Always steps by `tick_size`, so that every tick is visited and samples such as the
+    /// occupancy series are evenly spaced. Every tick is processed, even when nothing can happen.
+    Fixed,
+}
@@ -0,0 +1,37 @@
+use core::time::Duration;
+
+use crate::Simulation;
+
+/// Advances a running `Simulation`, yielding the tick it advanced to until it completes. Created
+/// with `Simulation::ticks()`.
+pub struct Ticks<'a> {
+    sim: &'a mut Simulation,
+}
+
+impl core::fmt::Debug for Ticks<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Ticks")
+            .field("tick", &self.sim.running().1)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> Ticks<'a> {
+    pub(crate) fn new(sim: &'a mut Simulation) -> Self {
+        Self { sim }
+    }
+}
+
+impl Iterator for Ticks<'_> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (running, _) = self.sim.running();
+        if !running {
+            return None;
+        }
+
+        self.sim.tick();
+        Some(self.sim.running().1)
+    }
+}
@@ -30,8 +30,67 @@ impl core::fmt::Display for LogLevel {
 #[command(author, version, about, long_about = None)]
 pub struct Args {
     /// Path to the config.toml to use
-    #[arg(required = true)]
-    pub config_path: Option<PathBuf>,
+    pub config_path: PathBuf,
+    /// Additional config files merged over `config_path` in order. Clients, servers, and metrics
+    /// are appended, other fields are replaced
+    #[arg(long = "config")]
+    pub configs: Vec<PathBuf>,
     #[arg(long, default_value_t = LogLevel::Error)]
     pub log_level: LogLevel,
+    /// Print a summary of all simulations after the individual results
+    #[arg(long)]
+    pub summary: bool,
+    /// Only print the summary of all simulations
+    #[arg(long)]
+    pub quiet: bool,
+    /// Exit with a non-zero exit code when any metric misses its target
+    #[arg(long)]
+    pub fail_on_miss: bool,
+    /// Number of threads to run simulations on. 0 uses all cores. Default is all but one core
+    #[arg(long)]
+    pub threads: Option<usize>,
+    /// Write results to this file instead of stdout. The file is created or truncated
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+
+    #[test]
+    fn config_path_is_required() {
+        let err = Args::try_parse_from(["awt"]).unwrap_err();
+
+        assert_eq!(ErrorKind::MissingRequiredArgument, err.kind());
+        // clap exits with 2 on usage errors
+        assert_eq!(2, err.exit_code());
+    }
+
+    #[test]
+    fn parses_config_path() {
+        let args = Args::try_parse_from(["awt", "config.toml"]).unwrap();
+
+        assert_eq!(PathBuf::from("config.toml"), args.config_path);
+    }
+
+    #[test]
+    fn parses_additional_configs() {
+        let args = Args::try_parse_from([
+            "awt",
+            "servers.toml",
+            "--config",
+            "a.toml",
+            "--config",
+            "b.toml",
+        ])
+        .unwrap();
+
+        assert_eq!(PathBuf::from("servers.toml"), args.config_path);
+        assert_eq!(
+            vec![PathBuf::from("a.toml"), PathBuf::from("b.toml")],
+            args.configs
+        );
+    }
 }
@@ -1,6 +1,5 @@
 use serde::Deserialize;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::collections::HashMap;
 
 #[derive(Default, Clone, Deserialize, Debug, Eq, PartialEq)]
 pub struct Attribute {
@@ -8,15 +7,74 @@ pub struct Attribute {
     pub level: Option<usize>,
 }
 
-impl From<&Attribute> for crate::Attribute {
-    fn from(attr: &Attribute) -> Self {
-        let mut s = DefaultHasher::new();
-        attr.name.hash(&mut s);
-        let hash = s.finish();
+impl Attribute {
+    /// Converts to a simulation `Attribute`, with the id interned from `name`.
+    pub fn to_simulation(&self, interner: &mut AttributeInterner) -> crate::Attribute {
+        crate::Attribute {
+            id: interner.intern(&self.name),
+            level: self.level,
+        }
+    }
+}
+
+/// Maps attribute names to small sequential ids, so that distinct names never share an id and the
+/// name can be recovered for output.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Default, Clone, Debug)]
+pub struct AttributeInterner {
+    ids: HashMap<String, u64>,
+    names: Vec<String>,
+}
 
-        Self {
-            id: hash,
-            level: attr.level,
+impl AttributeInterner {
+    /// Returns the id for `name`, assigning the next id if `name` hasn't been seen before.
+    pub fn intern(&mut self, name: &str) -> u64 {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
         }
+
+        let id = self.names.len() as u64;
+        self.ids.insert(name.to_owned(), id);
+        self.names.push(name.to_owned());
+        id
+    }
+
+    /// Returns the name which was interned as `id`.
+    pub fn name(&self, id: u64) -> Option<&str> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|index| self.names.get(index))
+            .map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distinct_names_have_distinct_ids() {
+        let mut interner = AttributeInterner::default();
+
+        let spanish = interner.intern("Spanish");
+        let english = interner.intern("English");
+
+        assert_ne!(spanish, english);
+        assert_eq!(spanish, interner.intern("Spanish"));
+    }
+
+    #[test]
+    fn recovers_name_from_id() {
+        let mut interner = AttributeInterner::default();
+
+        let attribute = Attribute {
+            name: "Spanish".to_owned(),
+            level: Some(2),
+        }
+        .to_simulation(&mut interner);
+
+        assert_eq!(Some(2), attribute.level);
+        assert_eq!(Some("Spanish"), interner.name(attribute.id));
+        assert_eq!(None, interner.name(attribute.id + 1));
     }
 }
@@ -0,0 +1,24 @@
+use core::time::Duration;
+use serde::Deserialize;
+
+use super::duration;
+use awt_simulation::arrival::Batch as SimulationBatch;
+
+/// Delivers a client group's requests in batches, e.g. emails fetched every few minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Batch {
+    /// The most requests delivered at once.
+    pub size: usize,
+    /// The time between deliveries.
+    #[serde(deserialize_with = "duration::deserialize")]
+    pub interval: Duration,
+}
+
+impl From<&Batch> for SimulationBatch {
+    fn from(batch: &Batch) -> Self {
+        Self {
+            size: batch.size,
+            interval: batch.interval,
+        }
+    }
+}
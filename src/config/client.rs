@@ -1,33 +1,109 @@
+use awt_simulation::arrival::ArrivalPattern;
 use core::time::Duration;
 use serde::Deserialize;
 
-use super::Attribute;
+use super::{
+    duration, Attribute, AttributeInterner, Batch, ConfigError, ContactType, Distribution,
+    SelfService,
+};
 use crate::Client as SimulationClient;
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+fn default_required_servers() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Client {
+    /// Default is `Call`.
+    #[serde(default)]
+    pub channel: ContactType,
     /// Default is an empty attribute array.
     #[serde(default)]
     pub required_attributes: Vec<Attribute>,
+    #[serde(deserialize_with = "duration::deserialize")]
     pub handle_time: Duration,
     /// Default is 0 secs, 0 nanos.
-    #[serde(default)]
+    #[serde(default, deserialize_with = "duration::deserialize")]
     pub clean_up_time: Duration,
+    #[serde(deserialize_with = "duration::deserialize")]
     pub abandon_time: Duration,
+    /// Default is `Fixed`.
+    #[serde(default)]
+    pub abandon_distribution: Distribution,
     pub quantity: usize,
+    /// Default is 1.
+    #[serde(default = "default_required_servers")]
+    pub required_servers: usize,
+    /// Default is no self service.
+    #[serde(default)]
+    pub self_service: Option<SelfService>,
+    /// `(fraction, weight)` segments of a piecewise arrival rate. Default is empty, which is
+    /// uniform.
+    #[serde(default)]
+    pub arrival_pattern: Vec<(f64, f64)>,
+    /// Default is 0.0, where no requests opt into a callback.
+    #[serde(default)]
+    pub callback_rate: f64,
+    /// Default is 0.0, where no requests are transferred.
+    #[serde(default)]
+    pub transfer_rate: f64,
+    /// Default is 0.0, where no requests hang up before being queued.
+    #[serde(default)]
+    pub pre_queue_drop_rate: f64,
+    /// Default is no batching, where requests arrive individually.
+    #[serde(default)]
+    pub batch: Option<Batch>,
 }
 
-impl From<&Client> for SimulationClient {
-    fn from(c: &Client) -> Self {
-        Self {
-            required_attributes: c
+impl Client {
+    /// The number of requests to generate for this client, after any self service deflection.
+    pub fn quantity(&self) -> usize {
+        self.self_service
+            .map_or(self.quantity, |s| s.deflected_quantity(self.quantity))
+    }
+
+    /// Errors when a value can't be simulated, e.g. a `Weibull` shape which isn't positive, a self
+    /// service `factor` or `arrival_pattern` segment which isn't finite, or no `required_servers`.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.abandon_distribution.validate()?;
+        if let Some(self_service) = &self.self_service {
+            self_service.validate()?;
+        }
+
+        if self.required_servers == 0 {
+            return Err(ConfigError::NotPositive("required_servers", 0.0));
+        }
+
+        let mut values = self.arrival_pattern.iter().flat_map(|(f, w)| [*f, *w]);
+        match values.find(|value| !value.is_finite()) {
+            Some(value) => Err(ConfigError::NotFinite("arrival_pattern", value)),
+            None => Ok(()),
+        }
+    }
+
+    /// Converts to a simulation `Client`, interning attribute names with `interner`.
+    pub fn to_simulation(&self, interner: &mut AttributeInterner) -> SimulationClient {
+        SimulationClient {
+            channel: (&self.channel).into(),
+            required_attributes: self
                 .required_attributes
                 .iter()
-                .map(crate::Attribute::from)
+                .map(|a| a.to_simulation(interner))
                 .collect(),
-            handle_time: c.handle_time,
-            clean_up_time: c.clean_up_time,
-            abandon_time: c.abandon_time,
+            handle_time: self.handle_time,
+            clean_up_time: self.clean_up_time,
+            abandon_time: self.abandon_time,
+            abandon_distribution: (&self.abandon_distribution).into(),
+            required_servers: self.required_servers,
+            arrival_pattern: if self.arrival_pattern.is_empty() {
+                ArrivalPattern::Uniform
+            } else {
+                ArrivalPattern::Piecewise(self.arrival_pattern.clone())
+            },
+            callback_rate: self.callback_rate,
+            transfer_rate: self.transfer_rate,
+            pre_queue_drop_rate: self.pre_queue_drop_rate,
+            batch: self.batch.as_ref().map(Into::into),
         }
     }
 }
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+use crate::ContactType as SimulationContactType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum ContactType {
+    #[default]
+    Call,
+    Email,
+    Chat,
+    SocialMedia,
+}
+
+impl ContactType {
+    pub fn all() -> Vec<ContactType> {
+        vec![Self::Call, Self::Email, Self::Chat, Self::SocialMedia]
+    }
+}
+
+impl From<&ContactType> for SimulationContactType {
+    fn from(contact_type: &ContactType) -> Self {
+        match contact_type {
+            ContactType::Call => Self::Call,
+            ContactType::Email => Self::Email,
+            ContactType::Chat => Self::Chat,
+            ContactType::SocialMedia => Self::SocialMedia,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+
+use super::ConfigError;
+use crate::Distribution as SimulationDistribution;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub enum Distribution {
+    #[default]
+    Fixed,
+    Exponential,
+    /// The shape, which must be positive and finite.
+    Weibull(f64),
+}
+
+impl Distribution {
+    /// Errors when a `Weibull` shape is not positive and finite, which can't be sampled.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self {
+            Self::Weibull(shape) if !(shape.is_finite() && *shape > 0.0) => {
+                Err(ConfigError::NotPositive("Weibull", *shape))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl From<&Distribution> for SimulationDistribution {
+    fn from(distribution: &Distribution) -> Self {
+        match distribution {
+            Distribution::Fixed => Self::Fixed,
+            Distribution::Exponential => Self::Exponential,
+            Distribution::Weibull(shape) => Self::Weibull(*shape),
+        }
+    }
+}
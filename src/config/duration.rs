@@ -0,0 +1,127 @@
+//! Deserializes config durations from any of:
+//!
+//! - A number of seconds, e.g. `0.05` or `300`
+//! - A number with a unit of `ns`, `us`, `ms`, `s`, `m`, or `h`, e.g. `"50ms"` or `"5m"`
+//! - The serde default of `{ secs = 0, nanos = 50000000 }`
+//!
+//! Used with `#[serde(deserialize_with = "...")]` on duration fields.
+use core::time::Duration;
+use serde::{de::Error, Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Seconds(f64),
+    Text(String),
+    Struct(Duration),
+}
+
+const UNITS: [(&str, f64); 6] = [
+    ("ns", 1e-9),
+    ("us", 1e-6),
+    ("ms", 1e-3),
+    ("s", 1.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+];
+
+fn from_secs<E: Error>(secs: f64) -> Result<Duration, E> {
+    Duration::try_from_secs_f64(secs).map_err(E::custom)
+}
+
+/// Parses a number with a unit, e.g. `"50ms"`.
+fn parse<E: Error>(text: &str) -> Result<Duration, E> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| c.is_ascii_alphabetic())
+        .ok_or_else(|| E::custom(format!("Duration `{text}` requires a unit")))?;
+    let (value, unit) = text.split_at(split);
+
+    let (_, scale) = UNITS
+        .iter()
+        .find(|(name, _)| *name == unit)
+        .ok_or_else(|| E::custom(format!("Unknown duration unit `{unit}`")))?;
+    let value: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| E::custom(format!("Invalid duration `{text}`")))?;
+
+    from_secs(value * scale)
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Repr::deserialize(deserializer)? {
+        Repr::Seconds(secs) => from_secs(secs),
+        Repr::Text(text) => parse(&text),
+        Repr::Struct(duration) => Ok(duration),
+    }
+}
+
+pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize(deserializer).map(Some)
+}
+
+/// A `Duration` which deserializes with `deserialize`, for use in collections.
+#[derive(Deserialize)]
+struct Element(#[serde(deserialize_with = "deserialize")] Duration);
+
+pub fn deserialize_option_vec<'de, D>(deserializer: D) -> Result<Option<Vec<Duration>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let elements = Vec::<Element>::deserialize(deserializer)?;
+    Ok(Some(elements.into_iter().map(|Element(d)| d).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        #[serde(deserialize_with = "deserialize")]
+        duration: Duration,
+    }
+
+    fn duration(toml: &str) -> Result<Duration, toml::de::Error> {
+        toml::from_str::<Config>(&format!("duration = {toml}")).map(|config| config.duration)
+    }
+
+    #[test]
+    fn parses_seconds() {
+        assert_eq!(Duration::from_secs(300), duration("300").unwrap());
+        assert_eq!(Duration::from_millis(50), duration("0.05").unwrap());
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(Duration::from_nanos(10), duration(r#""10ns""#).unwrap());
+        assert_eq!(Duration::from_micros(10), duration(r#""10us""#).unwrap());
+        assert_eq!(Duration::from_millis(50), duration(r#""50ms""#).unwrap());
+        assert_eq!(Duration::from_secs(20), duration(r#""20s""#).unwrap());
+        assert_eq!(Duration::from_secs(90), duration(r#""1.5m""#).unwrap());
+        assert_eq!(Duration::from_secs(3600), duration(r#""1h""#).unwrap());
+    }
+
+    #[test]
+    fn parses_struct() {
+        assert_eq!(
+            Duration::from_millis(50),
+            duration("{ secs = 0, nanos = 50_000_000 }").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        assert!(duration("-1").is_err());
+        assert!(duration(r#""5""#).is_err());
+        assert!(duration(r#""5d""#).is_err());
+        assert!(duration(r#""fivem""#).is_err());
+    }
+}
@@ -4,6 +4,8 @@ use std::convert::TryFrom;
 use thiserror::Error;
 use toml::Value;
 
+use super::{duration, Attribute, AttributeInterner};
+
 use awt_metrics::{
     Metric as SimMetric, MetricError as SimMetricError, MetricType as SimMetricType,
     Target as SimTarget,
@@ -14,12 +16,19 @@ use awt_metrics::{
 pub enum MetricType {
     ServiceLevel,
     AverageWorkTime,
+    AverageCleanUpTime,
     AverageSpeedAnswer,
     AverageTimeToAbandon,
     AbandonRate,
     AverageTimeInQueue,
     UtilisationTime,
     AnswerCount,
+    PeakQueueLength,
+    AverageIdleTime,
+    WaitTimeHistogram,
+    CallbackRate,
+    AverageCallbackDelay,
+    TransferRate,
 }
 
 impl core::fmt::Display for MetricType {
@@ -32,8 +41,36 @@ impl core::fmt::Display for MetricType {
 #[derive(Deserialize, Debug, Clone)]
 pub struct Metric {
     pub metric: MetricType,
+    /// Default is the name of the `MetricType`.
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
     pub sla: Option<Duration>,
+    /// An alternative to `sla`, as a number of ticks of the config's `tick_size`.
+    pub sla_ticks: Option<usize>,
     pub target: Option<Value>,
+    /// The bounds of each bucket of a `WaitTimeHistogram`.
+    #[serde(default, deserialize_with = "duration::deserialize_option_vec")]
+    pub buckets: Option<Vec<Duration>>,
+    /// Only requests which require all of these attributes are reported, labelled by the attribute
+    /// names. Default is every request.
+    #[serde(default)]
+    pub group: Option<Vec<Attribute>>,
+    /// Default is 1.0.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Default is 0.0.
+    #[serde(default)]
+    pub reward: f64,
+    /// Default is 0.0.
+    #[serde(default)]
+    pub penalty: f64,
+    /// Default is false, where exceeding the target scores only the reward.
+    #[serde(default)]
+    pub reward_overachievement: bool,
+}
+
+fn default_weight() -> f64 {
+    1.0
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -42,10 +79,16 @@ pub struct Metric {
 pub enum MetricError {
     #[error("SLARequiresWindow: SLA requires a window specified by a sla key")]
     SLARequiresWindow,
+    #[error("SLA should be specified by either a sla or sla_ticks key, not both")]
+    SLAAndSLATicks,
+    #[error("SLA of {0} ticks is too long")]
+    SLATicksOverflow(usize),
     #[error("SLA requires a target in the range of 0.0..1.0. Received {0}")]
     SLAOutsideOfTarget(f64),
     #[error("Target should be a floating point number {0}")]
     TargetFloatingPoint(Value),
+    #[error("HistogramRequiresBuckets: {0} requires bucket bounds specified by a buckets key")]
+    HistogramRequiresBuckets(MetricType),
     #[error("Target is required for {0}")]
     TargetRequired(MetricType),
     #[error("Conversion error for {0}")]
@@ -62,6 +105,51 @@ impl From<SimMetricError> for MetricError {
     }
 }
 
+impl Metric {
+    /// Converts to a simulation metric, applying the configured `group` and `name`. Group attribute
+    /// names are interned with `interner`, and `sla_ticks` are ticks of `tick_size`.
+    pub fn to_simulation(
+        &self,
+        interner: &mut AttributeInterner,
+        tick_size: Duration,
+    ) -> Result<SimMetric, MetricError> {
+        let resolved = Self {
+            sla: self.sla(tick_size)?,
+            sla_ticks: None,
+            ..self.clone()
+        };
+        let mut metric = SimMetric::try_from(&resolved)?
+            .with_score(self.weight, self.reward, self.penalty)
+            .with_reward_overachievement(self.reward_overachievement);
+
+        if let Some(group) = &self.group {
+            let label: Vec<&str> = group.iter().map(|a| a.name.as_str()).collect();
+            let group = group.iter().map(|a| a.to_simulation(interner)).collect();
+            metric = metric.with_group(group, &label.join(", "));
+        }
+
+        Ok(match &self.name {
+            Some(name) => metric.with_name(name),
+            None => metric,
+        })
+    }
+
+    /// The SLA window, from either `sla` or `sla_ticks` of `tick_size`.
+    fn sla(&self, tick_size: Duration) -> Result<Option<Duration>, MetricError> {
+        match (self.sla, self.sla_ticks) {
+            (Some(_), Some(_)) => Err(MetricError::SLAAndSLATicks),
+            (Some(sla), None) => Ok(Some(sla)),
+            (None, Some(ticks)) => u32::try_from(ticks)
+                .ok()
+                .and_then(|multiple| tick_size.checked_mul(multiple))
+                .map(Some)
+                .ok_or(MetricError::SLATicksOverflow(ticks)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Converts a metric whose `sla_ticks` are already resolved into `sla`, see `to_simulation`.
 impl TryFrom<&Metric> for SimMetric {
     type Error = MetricError;
 
@@ -86,80 +174,92 @@ impl TryFrom<&Metric> for SimMetric {
                     SimTarget::percent(target),
                 )?)
             }
-            MetricType::AverageWorkTime => {
+            MetricType::AverageWorkTime
+            | MetricType::AverageCleanUpTime
+            | MetricType::AverageSpeedAnswer
+            | MetricType::AverageTimeToAbandon
+            | MetricType::AverageTimeInQueue
+            | MetricType::AverageIdleTime
+            | MetricType::AverageCallbackDelay => {
                 let target: Duration = if let Some(value) = metric.target.clone() {
-                    value.try_into()?
+                    duration::deserialize(value)?
                 } else {
                     Err(MetricError::TargetRequired(metric.metric))?
                 };
 
-                Ok(Self::with_target(
-                    SimMetricType::AverageWorkTime,
-                    SimTarget::mean_duration(target),
-                )?)
-            }
-            MetricType::AverageSpeedAnswer => {
-                let target: Duration = if let Some(value) = metric.target.clone() {
-                    value.try_into()?
-                } else {
-                    Err(MetricError::TargetRequired(metric.metric))?
+                let metric_type = match metric.metric {
+                    MetricType::AverageWorkTime => SimMetricType::AverageWorkTime,
+                    MetricType::AverageCleanUpTime => SimMetricType::AverageCleanUpTime,
+                    MetricType::AverageSpeedAnswer => SimMetricType::AverageSpeedAnswer,
+                    MetricType::AverageTimeToAbandon => SimMetricType::AverageTimeToAbandon,
+                    MetricType::AverageIdleTime => SimMetricType::AverageIdleTime,
+                    MetricType::AverageCallbackDelay => SimMetricType::AverageCallbackDelay,
+                    _ => SimMetricType::AverageTimeInQueue,
                 };
 
                 Ok(Self::with_target(
-                    SimMetricType::AverageSpeedAnswer,
+                    metric_type,
                     SimTarget::mean_duration(target),
                 )?)
             }
-            MetricType::AverageTimeToAbandon => {
-                let target: Duration = if let Some(value) = metric.target.clone() {
+            MetricType::AbandonRate | MetricType::CallbackRate | MetricType::TransferRate => {
+                let target: f64 = if let Some(value) = metric.target.clone() {
                     value.try_into()?
                 } else {
                     Err(MetricError::TargetRequired(metric.metric))?
                 };
 
-                Ok(Self::with_target(
-                    SimMetricType::AverageTimeToAbandon,
-                    SimTarget::mean_duration(target),
-                )?)
+                let metric_type = match metric.metric {
+                    MetricType::CallbackRate => SimMetricType::CallbackRate,
+                    MetricType::TransferRate => SimMetricType::TransferRate,
+                    _ => SimMetricType::AbandonRate,
+                };
+
+                Ok(Self::with_target(metric_type, SimTarget::percent(target))?)
             }
-            MetricType::AverageTimeInQueue => {
-                let target: Duration = if let Some(value) = metric.target.clone() {
+            MetricType::AnswerCount => {
+                let target: usize = if let Some(value) = metric.target.clone() {
                     value.try_into()?
                 } else {
                     Err(MetricError::TargetRequired(metric.metric))?
                 };
 
                 Ok(Self::with_target(
-                    SimMetricType::AverageTimeInQueue,
-                    SimTarget::mean_duration(target),
+                    SimMetricType::AnswerCount,
+                    SimTarget::count(target),
                 )?)
             }
-            MetricType::AbandonRate => {
-                let target: f64 = if let Some(value) = metric.target.clone() {
+
+            MetricType::PeakQueueLength => {
+                let target: usize = if let Some(value) = metric.target.clone() {
                     value.try_into()?
                 } else {
                     Err(MetricError::TargetRequired(metric.metric))?
                 };
 
                 Ok(Self::with_target(
-                    SimMetricType::AbandonRate,
-                    SimTarget::percent(target),
+                    SimMetricType::PeakQueueLength,
+                    SimTarget::count(target),
                 )?)
             }
-            MetricType::AnswerCount => {
-                let target: usize = if let Some(value) = metric.target.clone() {
+
+            MetricType::UtilisationTime => {
+                let target: f64 = if let Some(value) = metric.target.clone() {
                     value.try_into()?
                 } else {
                     Err(MetricError::TargetRequired(metric.metric))?
                 };
 
                 Ok(Self::with_target(
-                    SimMetricType::AnswerCount,
-                    SimTarget::count(target),
+                    SimMetricType::UtilisationTime,
+                    SimTarget::percent(target),
                 )?)
             }
 
-            MetricType::UtilisationTime => Err(MetricError::NotYetImplemented),
+            MetricType::WaitTimeHistogram => match metric.buckets.clone() {
+                Some(buckets) => Ok(Self::wait_time_histogram(buckets)),
+                None => Err(MetricError::HistogramRequiresBuckets(metric.metric)),
+            },
         }
     }
 }
@@ -7,27 +7,53 @@ use std::path::PathBuf;
 use thiserror::Error;
 
 mod attribute;
+mod batch;
 mod client;
+mod contact_type;
+mod distribution;
+mod duration;
 mod metric;
 mod parsed;
+mod routing;
+mod self_service;
 mod server;
+mod staffing_sweep;
 
 use attribute::Attribute;
+pub use attribute::AttributeInterner;
+use batch::Batch;
 use client::Client;
+use contact_type::ContactType;
+use distribution::Distribution;
 use metric::Metric;
 pub use parsed::Parsed;
+use routing::Routing;
+use self_service::SelfService;
 use server::Server;
+use staffing_sweep::StaffingSweep;
 
+/// A config file. Every field may be omitted from a single file so that several files can be
+/// combined with `merge`, but `tick_size` and `tick_until` are required once merged.
 #[derive(Default, Clone, Deserialize, Debug)]
 pub struct Config {
+    #[serde(default)]
     clients: Vec<Client>,
+    #[serde(default)]
     servers: Vec<Server>,
-    metrics: Vec<Metric>,
     #[serde(default)]
-    pub simulations: usize,
-    pub tick_size: Duration,
-    pub tick_until: Duration,
+    metrics: Vec<Metric>,
+    pub simulations: Option<usize>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    pub tick_size: Option<Duration>,
+    #[serde(default, deserialize_with = "duration::deserialize_option")]
+    pub tick_until: Option<Duration>,
     pub rng_seeds: Option<Vec<u64>>,
+    /// Derives a seed for every simulation. Takes precedence over `rng_seeds`.
+    pub master_seed: Option<u64>,
+    /// Varies the quantity of every server group between simulations.
+    pub staffing_sweep: Option<StaffingSweep>,
+    /// The routing strategy of every simulation. Default is `Greedy`.
+    pub routing: Option<Routing>,
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -41,6 +67,14 @@ pub enum ConfigError {
     Metric(metric::MetricError),
     #[error("There should be as many rng_seeds as simulations")]
     BadSeeds,
+    #[error("`{0}` is required")]
+    Missing(&'static str),
+    #[error("`{0}` should be a positive number. Received {1}")]
+    NotPositive(&'static str, f64),
+    #[error("`{0}` should be a finite number. Received {1}")]
+    NotFinite(&'static str, f64),
+    #[error("`{0}` should be a fraction from 0.0 to 1.0. Received {1}")]
+    NotFraction(&'static str, f64),
 }
 
 impl TryFrom<&PathBuf> for Config {
@@ -52,20 +86,160 @@ impl TryFrom<&PathBuf> for Config {
 
         file.read_to_string(&mut toml)?;
 
-        let config = toml::from_str::<Config>(&toml)?;
+        Ok(toml::from_str::<Config>(&toml)?)
+    }
+}
+
+impl Config {
+    /// Combines `other` into this config, e.g. servers from one file and clients from another.
+    /// The `clients`, `servers`, and `metrics` of `other` are appended. Every other field is
+    /// replaced when set in `other`, including `rng_seeds` since it must match `simulations`.
+    #[must_use]
+    pub fn merge(mut self, mut other: Config) -> Config {
+        self.clients.append(&mut other.clients);
+        self.servers.append(&mut other.servers);
+        self.metrics.append(&mut other.metrics);
 
-        if let Some(seeds) = &config.rng_seeds {
-            if seeds.len() != config.simulations {
-                return Err(Self::Error::BadSeeds);
-            }
+        Config {
+            clients: self.clients,
+            servers: self.servers,
+            metrics: self.metrics,
+            simulations: other.simulations.or(self.simulations),
+            tick_size: other.tick_size.or(self.tick_size),
+            tick_until: other.tick_until.or(self.tick_until),
+            rng_seeds: other.rng_seeds.or(self.rng_seeds),
+            master_seed: other.master_seed.or(self.master_seed),
+            staffing_sweep: other.staffing_sweep.or(self.staffing_sweep),
+            routing: other.routing.or(self.routing),
         }
+    }
 
-        Ok(config)
+    /// Reads and merges each of the config files at `paths` in order.
+    ///
+    /// # Errors
+    ///
+    /// Will error when any file cannot be read or parsed.
+    pub fn from_paths<'a, I>(paths: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = &'a PathBuf>,
+    {
+        paths
+            .into_iter()
+            .try_fold(Config::default(), |config, path| {
+                Ok(config.merge(Config::try_from(path)?))
+            })
     }
-}
 
-impl Config {
+    /// Validates the complete config, after any merging, and expands it for simulating.
     pub fn parsed(self) -> Result<Parsed, ConfigError> {
+        if let (Some(seeds), None) = (&self.rng_seeds, self.master_seed) {
+            if seeds.len() != self.simulations.unwrap_or_default() {
+                return Err(ConfigError::BadSeeds);
+            }
+        }
+
         Parsed::try_from(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVERS: &str = r#"
+        tick_size = "1s"
+        simulations = 1
+
+        [[servers]]
+        quantity = 2
+        "#;
+
+    const EXPERIMENT: &str = r#"
+        tick_until = "1h"
+        simulations = 2
+
+        [[servers]]
+        quantity = 1
+
+        [[clients]]
+        quantity = 10
+        handle_time = "5m"
+        abandon_time = "1m"
+
+        [[metrics]]
+        metric = "AnswerCount"
+        target = 1
+        "#;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn merges_partial_configs() {
+        let merged = config(SERVERS).merge(config(EXPERIMENT));
+
+        assert_eq!(Some(Duration::from_secs(1)), merged.tick_size);
+        assert_eq!(Some(Duration::from_secs(3600)), merged.tick_until);
+        // Later files replace scalars and append to vectors
+        assert_eq!(Some(2), merged.simulations);
+        assert_eq!(2, merged.servers.len());
+        assert_eq!(1, merged.clients.len());
+        assert_eq!(1, merged.metrics.len());
+
+        let parsed = merged.parsed().unwrap();
+        assert_eq!(2, parsed.simulations());
+        assert_eq!(1, parsed.metrics().len());
+    }
+
+    #[test]
+    fn partial_config_is_incomplete() {
+        assert!(matches!(
+            config(SERVERS).parsed(),
+            Err(ConfigError::Missing("tick_until"))
+        ));
+    }
+
+    #[test]
+    fn validates_seeds_once_merged() {
+        let seeds = config("rng_seeds = [1, 2]");
+
+        assert!(matches!(
+            config(SERVERS).merge(seeds.clone()).parsed(),
+            Err(ConfigError::BadSeeds)
+        ));
+        assert!(config(SERVERS)
+            .merge(config(EXPERIMENT))
+            .merge(seeds)
+            .parsed()
+            .is_ok());
+    }
+
+    #[test]
+    fn labels_grouped_metrics() {
+        let vip = config(
+            r#"
+            [[metrics]]
+            metric = "ServiceLevel"
+            sla = "10s"
+            target = 0.8
+            group = [{ name = "VIP" }]
+            "#,
+        );
+        let parsed = config(SERVERS)
+            .merge(config(EXPERIMENT))
+            .merge(vip)
+            .parsed()
+            .unwrap();
+
+        let mut names: Vec<String> = parsed
+            .metrics()
+            .iter()
+            .map(|m| m.name().to_owned())
+            .collect();
+        names.sort_unstable();
+
+        assert_eq!(vec!["AnswerCount", "ServiceLevel(10s)[VIP]"], names);
+        assert_eq!(Some("VIP"), parsed.attributes().name(0));
+    }
+}
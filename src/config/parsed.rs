@@ -3,7 +3,7 @@ use rand::{prelude::*, rngs::SmallRng, thread_rng, SeedableRng};
 use rayon::iter::plumbing::UnindexedConsumer;
 use rayon::prelude::*;
 
-use super::ConfigError;
+use super::{AttributeInterner, ConfigError, Routing, StaffingSweep};
 
 use awt_metrics::Metric;
 use awt_simulation::{client::Client, server::Server, Config as SimulationConfig};
@@ -14,52 +14,84 @@ pub struct Parsed {
     tick_size: Duration,
     tick_until: Duration,
     clients: Vec<Client>,
-    servers: Vec<Server>,
+    /// The servers of each server group, expanded to the largest quantity which is simulated.
+    servers: Vec<Vec<Server>>,
+    staffing_sweep: Option<StaffingSweep>,
+    routing: Routing,
     metrics: Vec<Metric>,
     rng_seeds: Vec<u64>,
+    attributes: AttributeInterner,
 }
 
 impl TryFrom<super::Config> for Parsed {
     type Error = ConfigError;
 
     fn try_from(config: super::Config) -> Result<Self, Self::Error> {
-        // Use the seeds if provided, otherwise ensure all seeds are generated
-        let rng_seeds = if let Some(seeds) = &config.rng_seeds {
-            seeds.clone()
-        } else {
-            let mut rng = thread_rng();
-            (0..config.simulations).map(|_| rng.gen()).collect()
+        // Derive seeds from the master seed, then use the seeds if provided, otherwise ensure all
+        // seeds are generated
+        let simulations = config.simulations.unwrap_or_default();
+        let tick_size = config.tick_size.ok_or(ConfigError::Missing("tick_size"))?;
+        let tick_until = config
+            .tick_until
+            .ok_or(ConfigError::Missing("tick_until"))?;
+
+        let rng_seeds = match (config.master_seed, &config.rng_seeds) {
+            (Some(master_seed), _) => {
+                let mut rng = SmallRng::seed_from_u64(master_seed);
+                (0..simulations).map(|_| rng.gen()).collect()
+            }
+            (None, Some(seeds)) => seeds.clone(),
+            (None, None) => {
+                let mut rng = thread_rng();
+                (0..simulations).map(|_| rng.gen()).collect()
+            }
         };
 
+        for client in &config.clients {
+            client.validate()?;
+        }
+        for server in &config.servers {
+            server.validate()?;
+        }
+
+        let mut attributes = AttributeInterner::default();
+
         let parsed = Parsed {
-            simulations: config.simulations,
-            tick_size: config.tick_size,
-            tick_until: config.tick_until,
+            simulations,
+            tick_size,
+            tick_until,
             clients: config
                 .clients
                 .iter()
                 .flat_map(|client_config| {
-                    (0..client_config.quantity)
-                        .map(|_| Client::from(client_config))
+                    (0..client_config.quantity())
+                        .map(|_| client_config.to_simulation(&mut attributes))
                         .collect::<Vec<Client>>()
                 })
                 .collect(),
             servers: config
                 .servers
                 .iter()
-                .flat_map(|server_config| {
-                    (0..server_config.quantity)
-                        .map(|_| Server::from(server_config))
+                .map(|server_config| {
+                    let quantity = config
+                        .staffing_sweep
+                        .map_or(server_config.quantity, |sweep| sweep.max_quantity());
+
+                    (0..quantity)
+                        .map(|_| server_config.to_simulation(&mut attributes))
                         .collect::<Vec<Server>>()
                 })
                 .collect(),
+            staffing_sweep: config.staffing_sweep,
+            routing: config.routing.unwrap_or_default(),
             metrics: config
                 .metrics
                 .iter()
-                .map(Metric::try_from)
+                .map(|metric_config| metric_config.to_simulation(&mut attributes, tick_size))
                 .collect::<Result<Vec<Metric>, super::metric::MetricError>>()
                 .map_err(ConfigError::Metric)?,
             rng_seeds,
+            attributes,
         };
 
         Ok(parsed)
@@ -67,26 +99,57 @@ impl TryFrom<super::Config> for Parsed {
 }
 
 impl Parsed {
-    /// Generates a `SimulationConfig` which can be passed into a `awt-simulation`.
-    pub fn new_sim(self, i: usize) -> SimulationConfig {
-        // Unwrap since parsing should gaurantee the rng_seed exists
-        let seed = self.rng_seeds.get(i).unwrap();
-        let rng = Box::new(SmallRng::seed_from_u64(*seed));
+    /// Generates a `SimulationConfig` which can be passed into a `awt-simulation`. Only the
+    /// clients and servers of simulation `i` are cloned, since the simulation consumes them.
+    pub fn new_sim(&self, i: usize) -> SimulationConfig {
+        // Parsing should gaurantee the rng_seed exists
+        let rng = Box::new(SmallRng::seed_from_u64(self.seed(i)));
 
         let mut simulation_config = SimulationConfig::new(self.tick_until, self.tick_size, rng);
-        simulation_config.set_clients(self.clients);
-        simulation_config.set_servers(self.servers);
+        simulation_config.set_servers(self.staffed_servers(i));
+        simulation_config.set_clients(self.clients.clone());
+        simulation_config.set_routing_strategy(self.routing.to_simulation());
 
         simulation_config
     }
 
+    /// The servers of simulation `i`, taking the staffing level of any staffing sweep from each
+    /// server group.
+    fn staffed_servers(&self, i: usize) -> Vec<Server> {
+        let sweep = self.staffing_sweep;
+
+        self.servers
+            .iter()
+            .flat_map(|group| {
+                let quantity = sweep.map_or(group.len(), |sweep| sweep.quantity(i));
+                group.iter().take(quantity).cloned()
+            })
+            .collect()
+    }
+
+    /// The rng seed of simulation `i`, either from config or generated.
+    pub fn seed(&self, i: usize) -> u64 {
+        self.rng_seeds[i]
+    }
+
+    pub fn simulations(&self) -> usize {
+        self.simulations
+    }
+
     pub fn metrics(&self) -> Vec<Metric> {
         self.metrics.clone()
     }
+
+    /// The attribute names used in this config, by simulation `Attribute` id.
+    pub fn attributes(&self) -> &AttributeInterner {
+        &self.attributes
+    }
 }
 
+/// Yields each simulation index with its `SimulationConfig`. The scenario is shared between tasks,
+/// with each `SimulationConfig` generated within the task which runs it.
 impl ParallelIterator for Parsed {
-    type Item = (usize, Self);
+    type Item = (usize, SimulationConfig);
 
     fn drive_unindexed<C>(self, consumer: C) -> C::Result
     where
@@ -94,7 +157,468 @@ impl ParallelIterator for Parsed {
     {
         (0..self.simulations)
             .into_par_iter()
-            .map(|sim| (sim, self.clone()))
+            .map(|sim| (sim, self.new_sim(sim)))
             .drive_unindexed(consumer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{metric::MetricError, Config};
+    use awt_metrics::MetricType;
+
+    fn parsed(toml: &str) -> Parsed {
+        toml::from_str::<Config>(toml).unwrap().parsed().unwrap()
+    }
+
+    fn parse_error(toml: &str) -> ConfigError {
+        toml::from_str::<Config>(toml)
+            .unwrap()
+            .parsed()
+            .unwrap_err()
+    }
+
+    #[test]
+    fn self_service_deflects_clients() {
+        let parsed = parsed(
+            r"
+            simulations = 1
+            tick_size = { secs = 1, nanos = 0 }
+            tick_until = { secs = 3600, nanos = 0 }
+            metrics = []
+            servers = []
+
+            [[clients]]
+            handle_time = { secs = 300, nanos = 0 }
+            abandon_time = { secs = 60, nanos = 0 }
+            quantity = 100
+            self_service = { factor = 1.718281828459045 }
+
+            [[clients]]
+            handle_time = { secs = 300, nanos = 0 }
+            abandon_time = { secs = 60, nanos = 0 }
+            quantity = 10
+            ",
+        );
+
+        assert_eq!(60, parsed.clients.len());
+    }
+
+    #[test]
+    fn parses_duration_forms() {
+        let parsed = parsed(
+            r#"
+            simulations = 1
+            tick_size = "50ms"
+            tick_until = 3600
+            servers = []
+            metrics = [
+                { metric = "ServiceLevel", sla = "20s", target = 0.8 },
+                { metric = "AverageWorkTime", target = "5m" },
+                { metric = "WaitTimeHistogram", buckets = ["10s", 20] },
+            ]
+
+            [[clients]]
+            handle_time = 300.5
+            clean_up_time = "1m"
+            abandon_time = { secs = 60, nanos = 0 }
+            quantity = 1
+            "#,
+        );
+
+        assert_eq!(Duration::from_millis(50), parsed.tick_size);
+        assert_eq!(Duration::from_secs(3600), parsed.tick_until);
+        assert_eq!(
+            Duration::from_millis(300_500),
+            parsed.clients[0].handle_time
+        );
+        assert_eq!(Duration::from_secs(60), parsed.clients[0].clean_up_time);
+        assert_eq!(Duration::from_secs(60), parsed.clients[0].abandon_time);
+        assert_eq!(3, parsed.metrics.len());
+        assert_eq!(
+            "0s-10s: 0, 10s-20s: 0, 20s+: 0",
+            parsed.metrics[2].to_string()
+        );
+    }
+
+    #[test]
+    fn sla_ticks_are_ticks_of_tick_size() {
+        let parsed = parsed(
+            r#"
+            simulations = 1
+            tick_size = "50ms"
+            tick_until = "1h"
+            metrics = [
+                { metric = "ServiceLevel", sla_ticks = 10, target = 0.8 },
+                { metric = "ServiceLevel", sla = "500ms", target = 0.8, name = "Window" },
+            ]
+            "#,
+        );
+
+        let window = MetricType::ServiceLevel(Duration::from_millis(500));
+        assert_eq!(window, parsed.metrics[0].metric());
+        assert_eq!(parsed.metrics[1].metric(), parsed.metrics[0].metric());
+    }
+
+    #[test]
+    fn sla_and_sla_ticks_are_exclusive() {
+        let config = toml::from_str::<Config>(
+            r#"
+            tick_size = "1s"
+            tick_until = "1h"
+            metrics = [{ metric = "ServiceLevel", sla = "10s", sla_ticks = 10, target = 0.8 }]
+            "#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            config.parsed(),
+            Err(ConfigError::Metric(MetricError::SLAAndSLATicks))
+        ));
+    }
+
+    #[test]
+    fn rejects_weibull_shapes_which_are_not_positive() {
+        for shape in ["0.0", "-1.5", "nan", "inf"] {
+            let err = parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[clients]]
+                handle_time = "5m"
+                abandon_time = "1m"
+                abandon_distribution = {{ Weibull = {shape} }}
+                quantity = 1
+                "#
+            ));
+
+            assert!(
+                matches!(err, ConfigError::NotPositive("Weibull", _)),
+                "{shape}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_arrival_patterns_which_are_not_finite() {
+        for segment in ["[0.0, nan]", "[inf, 1.0]", "[0.5, -inf]"] {
+            let err = parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[clients]]
+                handle_time = "5m"
+                abandon_time = "1m"
+                arrival_pattern = [[0.0, 1.0], {segment}]
+                quantity = 1
+                "#
+            ));
+
+            assert!(matches!(err, ConfigError::NotFinite("arrival_pattern", _)));
+        }
+    }
+
+    #[test]
+    fn rejects_efficiencies_which_are_not_positive() {
+        let server = |efficiencies: &str| {
+            parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[servers]]
+                quantity = 1
+                {efficiencies}
+                "#
+            ))
+        };
+
+        for value in ["0.0", "-0.5", "nan"] {
+            assert!(matches!(
+                server(&format!("efficiency = {value}")),
+                ConfigError::NotPositive("efficiency", _)
+            ));
+            assert!(matches!(
+                server(&format!("attribute_efficiency = {{ Spanish = {value} }}")),
+                ConfigError::NotPositive("attribute_efficiency", _)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_acw_factors_which_are_not_positive() {
+        for acw_factor in ["0.0", "-2.0", "nan"] {
+            let err = parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[servers]]
+                quantity = 1
+                acw_factor = {acw_factor}
+                "#
+            ));
+
+            assert!(matches!(err, ConfigError::NotPositive("acw_factor", _)));
+        }
+    }
+
+    #[test]
+    fn rejects_availabilities_which_are_not_fractions() {
+        for availability in ["1.5", "-0.1", "nan", "inf"] {
+            let err = parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[servers]]
+                quantity = 1
+                availability = {availability}
+                "#
+            ));
+
+            assert!(matches!(err, ConfigError::NotFraction("availability", _)));
+        }
+    }
+
+    #[test]
+    fn rejects_self_service_factors_which_are_not_finite() {
+        for factor in ["inf", "nan"] {
+            let err = parse_error(&format!(
+                r#"
+                tick_size = "1s"
+                tick_until = "1h"
+
+                [[clients]]
+                handle_time = "5m"
+                abandon_time = "1m"
+                quantity = 10
+                self_service = {{ factor = {factor}, max_deflection = 0.5 }}
+                "#
+            ));
+
+            assert!(matches!(err, ConfigError::NotFinite("factor", _)));
+        }
+    }
+
+    #[test]
+    fn rejects_clients_which_require_no_servers() {
+        let err = parse_error(
+            r#"
+            tick_size = "1s"
+            tick_until = "1h"
+
+            [[clients]]
+            handle_time = "5m"
+            abandon_time = "1m"
+            quantity = 1
+            required_servers = 0
+            "#,
+        );
+
+        assert!(matches!(
+            err,
+            ConfigError::NotPositive("required_servers", _)
+        ));
+    }
+
+    const MASTER_SEED: &str = r"
+        simulations = 3
+        tick_size = { secs = 1, nanos = 0 }
+        tick_until = { secs = 3600, nanos = 0 }
+        master_seed = 42
+        rng_seeds = [1]
+        metrics = []
+        servers = []
+        clients = []
+    ";
+
+    #[test]
+    fn master_seed_derives_seeds() {
+        let parsed = parsed(MASTER_SEED);
+
+        assert_eq!(3, parsed.rng_seeds.len());
+        assert_eq!(parsed.rng_seeds, self::parsed(MASTER_SEED).rng_seeds);
+    }
+
+    #[test]
+    fn staffing_sweep_varies_servers_per_simulation() {
+        let parsed = parsed(
+            r"
+            simulations = 3
+            tick_size = { secs = 1, nanos = 0 }
+            tick_until = { secs = 3600, nanos = 0 }
+            metrics = []
+            clients = []
+            staffing_sweep = { start = 2, end = 6, step = 2 }
+
+            [[servers]]
+            quantity = 1
+            ",
+        );
+
+        let staffing: Vec<usize> = (0..parsed.simulations)
+            .map(|i| parsed.staffed_servers(i).len())
+            .collect();
+
+        assert_eq!(vec![2, 4, 6], staffing);
+    }
+
+    #[test]
+    fn parallel_simulations_match_sequential() {
+        let parsed = parsed(
+            r#"
+            simulations = 4
+            tick_size = "1s"
+            tick_until = "1h"
+            master_seed = 7
+            staffing_sweep = { start = 1, end = 4, step = 1 }
+
+            [[servers]]
+            quantity = 1
+
+            [[clients]]
+            quantity = 20
+            handle_time = "5m"
+            abandon_time = "2m"
+            "#,
+        );
+
+        let run = |config: SimulationConfig| {
+            let mut sim = awt_simulation::Simulation::from(config);
+            sim.enable().unwrap();
+            while sim.tick() {}
+            sim.request_data()
+                .iter()
+                .map(|data| (data.status, data.wait_time, data.served_by))
+                .collect::<Vec<_>>()
+        };
+
+        let sequential: Vec<_> = (0..parsed.simulations)
+            .map(|i| run(parsed.new_sim(i)))
+            .collect();
+        let mut parallel: Vec<_> = parsed.clone().map(|(i, config)| (i, run(config))).collect();
+        parallel.sort_unstable_by_key(|(i, _)| *i);
+
+        assert_eq!(
+            sequential,
+            parallel
+                .into_iter()
+                .map(|(_, data)| data)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn master_seed_derives_different_seeds() {
+        let other = MASTER_SEED.replace("master_seed = 42", "master_seed = 43");
+
+        assert_ne!(parsed(MASTER_SEED).rng_seeds, parsed(&other).rng_seeds);
+    }
+
+    #[test]
+    fn simulates_with_configured_routing() {
+        use awt_simulation::routing::{
+            Greedy, LongestWait, NoOp, PreferredSkill, Random, RoutingStrategy, WeightedRandom,
+            Weighting,
+        };
+
+        const SCENARIO: &str = r#"
+            simulations = 1
+            tick_size = "1s"
+            tick_until = "1h"
+            rng_seeds = [7]
+
+            [[servers]]
+            quantity = 2
+            attributes = [{ name = "Spanish", level = 1 }]
+
+            [[servers]]
+            quantity = 1
+            attributes = [{ name = "Spanish", level = 3 }]
+
+            [[clients]]
+            quantity = 60
+            handle_time = "5m"
+            abandon_time = "10m"
+            required_attributes = [{ name = "Spanish", level = 1 }]
+        "#;
+
+        // The outcome of every request, routed as configured or by `strategy` when provided
+        let run = |parsed: &Parsed, strategy: Option<Box<dyn RoutingStrategy>>| {
+            let mut sim = awt_simulation::Simulation::from(parsed.new_sim(0));
+            if let Some(strategy) = strategy {
+                sim.set_routing_strategy(strategy).unwrap();
+            }
+            sim.enable().unwrap();
+            while sim.tick() {}
+
+            sim.request_data()
+                .iter()
+                .map(|data| (data.status, data.wait_time, data.served_by))
+                .collect::<Vec<_>>()
+        };
+
+        let strategies: [(&str, Box<dyn RoutingStrategy>); 6] = [
+            (r#"{ strategy = "Greedy" }"#, Box::new(Greedy)),
+            (r#"{ strategy = "LongestWait" }"#, Box::new(LongestWait)),
+            (r#"{ strategy = "Random" }"#, Box::new(Random)),
+            (r#"{ strategy = "NoOp" }"#, Box::new(NoOp)),
+            (
+                r#"{ strategy = "WeightedRandom", weighting = "SkillLevel" }"#,
+                Box::new(WeightedRandom::new(Weighting::SkillLevel)),
+            ),
+            (
+                r#"{ strategy = "PreferredSkill", preferred_level = 3, overflow_after = "2m" }"#,
+                Box::new(PreferredSkill::new(3, Duration::from_secs(120))),
+            ),
+        ];
+
+        let mut outcomes = Vec::new();
+        for (routing, strategy) in strategies {
+            let parsed = parsed(&format!("routing = {routing}\n{SCENARIO}"));
+            let outcome = run(&parsed, None);
+            assert_eq!(run(&parsed, Some(strategy)), outcome, "{routing}");
+            assert!(!outcomes.contains(&outcome), "{routing}");
+            outcomes.push(outcome);
+        }
+    }
+
+    #[test]
+    fn routes_by_attribute_level() {
+        let parsed = parsed(
+            r#"
+            simulations = 1
+            tick_size = "1s"
+            tick_until = "1h"
+
+            [[servers]]
+            quantity = 1
+            attributes = [{ name = "Spanish", level = 1 }]
+
+            [[servers]]
+            quantity = 1
+            attributes = [{ name = "Spanish", level = 3 }]
+
+            [[clients]]
+            quantity = 1
+            handle_time = "5m"
+            abandon_time = "1h"
+            required_attributes = [{ name = "Spanish", level = 2 }]
+            "#,
+        );
+
+        let spanish = awt_simulation::attribute::Attribute::new(0, Some(3));
+        let expert = parsed.servers[1][0].id();
+        assert_eq!(&vec![spanish], parsed.servers[1][0].attributes());
+
+        let mut sim = awt_simulation::Simulation::from(parsed.new_sim(0));
+        sim.enable().unwrap();
+        while sim.tick() {}
+
+        assert_eq!(Some(expert), sim.request_data()[0].served_by);
+    }
+}
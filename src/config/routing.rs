@@ -0,0 +1,113 @@
+use core::time::Duration;
+use serde::Deserialize;
+
+use super::duration;
+
+use awt_simulation::routing::{
+    Greedy, LongestWait, NoOp, PreferredSkill, PrimarySkill, Random, RoutingStrategy,
+    WeightedRandom, Weighting as SimulationWeighting,
+};
+
+/// The routing strategy of each simulation, selected with a `strategy` key along with any
+/// parameters of that strategy, e.g. `{ strategy = "WeightedRandom", weighting = "IdleTime" }`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(tag = "strategy")]
+pub enum Routing {
+    #[default]
+    Greedy,
+    LongestWait,
+    Random,
+    NoOp,
+    WeightedRandom {
+        weighting: Weighting,
+    },
+    PreferredSkill {
+        preferred_level: usize,
+        #[serde(deserialize_with = "duration::deserialize")]
+        overflow_after: Duration,
+    },
+    PrimarySkill {
+        #[serde(deserialize_with = "duration::deserialize")]
+        overflow_after: Duration,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Weighting {
+    SkillLevel,
+    IdleTime,
+}
+
+impl Routing {
+    /// Creates the simulation `RoutingStrategy`, which is created for each simulation as
+    /// strategies may keep state between ticks.
+    pub fn to_simulation(self) -> Box<dyn RoutingStrategy + Send> {
+        match self {
+            Self::Greedy => Box::new(Greedy),
+            Self::LongestWait => Box::new(LongestWait),
+            Self::Random => Box::new(Random),
+            Self::NoOp => Box::new(NoOp),
+            Self::WeightedRandom { weighting } => Box::new(WeightedRandom::new(match weighting {
+                Weighting::SkillLevel => SimulationWeighting::SkillLevel,
+                Weighting::IdleTime => SimulationWeighting::IdleTime,
+            })),
+            Self::PreferredSkill {
+                preferred_level,
+                overflow_after,
+            } => Box::new(PreferredSkill::new(preferred_level, overflow_after)),
+            Self::PrimarySkill { overflow_after } => Box::new(PrimarySkill::new(overflow_after)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Config {
+        routing: Routing,
+    }
+
+    fn routing(toml: &str) -> Result<Routing, toml::de::Error> {
+        toml::from_str::<Config>(&format!("routing = {toml}")).map(|config| config.routing)
+    }
+
+    #[test]
+    fn parses_strategies_with_parameters() {
+        assert_eq!(
+            Routing::Greedy,
+            routing(r#"{ strategy = "Greedy" }"#).unwrap()
+        );
+        assert_eq!(
+            Routing::WeightedRandom {
+                weighting: Weighting::IdleTime
+            },
+            routing(r#"{ strategy = "WeightedRandom", weighting = "IdleTime" }"#).unwrap()
+        );
+        assert_eq!(
+            Routing::PreferredSkill {
+                preferred_level: 3,
+                overflow_after: Duration::from_secs(60),
+            },
+            routing(
+                r#"{ strategy = "PreferredSkill", preferred_level = 3, overflow_after = "1m" }"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Routing::PrimarySkill {
+                overflow_after: Duration::from_secs(30),
+            },
+            routing(r#"{ strategy = "PrimarySkill", overflow_after = "30s" }"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_strategies() {
+        let err = routing(r#"{ strategy = "Fastest" }"#).unwrap_err();
+
+        assert!(err.to_string().contains("unknown variant `Fastest`"));
+        assert!(routing(r#"{ strategy = "WeightedRandom" }"#).is_err());
+    }
+}
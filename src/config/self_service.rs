@@ -0,0 +1,96 @@
+use serde::Deserialize;
+
+use super::ConfigError;
+
+fn default_max_deflection() -> f64 {
+    1.0
+}
+
+/// Self service deflects a portion of a client group's requests before they are ever made.
+///
+/// The effectiveness of self service has diminishing returns, the deflection rate is:
+///
+/// `max_deflection * ln(1 + factor) / (1 + ln(1 + factor))`
+///
+/// e.g. with a `max_deflection` of 1.0, a `factor` of `e - 1` deflects half of all requests, and a
+/// `factor` of `e^2 - 1` deflects two thirds.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct SelfService {
+    /// The investment in self service. 0.0 deflects nothing. Must be finite.
+    pub factor: f64,
+    /// The upper bound for the portion of requests which can be deflected. Default is 1.0.
+    #[serde(default = "default_max_deflection")]
+    pub max_deflection: f64,
+}
+
+impl SelfService {
+    /// Errors when the `factor` isn't finite, which would deflect an undefined portion.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.factor.is_finite() {
+            Ok(())
+        } else {
+            Err(ConfigError::NotFinite("factor", self.factor))
+        }
+    }
+
+    /// The portion of requests which are deflected, in the range of `0.0..=1.0`.
+    #[must_use]
+    pub fn deflection_rate(&self) -> f64 {
+        let effectiveness = self.factor.max(0.0).ln_1p();
+        let rate = self.max_deflection.clamp(0.0, 1.0) * effectiveness / (1.0 + effectiveness);
+
+        rate.clamp(0.0, 1.0)
+    }
+
+    /// The remaining quantity of requests after deflection, rounded to the nearest request.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    #[allow(clippy::cast_possible_truncation)]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn deflected_quantity(&self, quantity: usize) -> usize {
+        (quantity as f64 * (1.0 - self.deflection_rate())).round() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::E;
+
+    fn self_service(factor: f64) -> SelfService {
+        SelfService {
+            factor,
+            max_deflection: default_max_deflection(),
+        }
+    }
+
+    #[test]
+    fn no_investment_deflects_nothing() {
+        assert_eq!(100, self_service(0.0).deflected_quantity(100));
+    }
+
+    #[test]
+    fn deflects_half() {
+        let self_service = self_service(E - 1.0);
+
+        assert!((self_service.deflection_rate() - 0.5).abs() < f64::EPSILON);
+        assert_eq!(50, self_service.deflected_quantity(100));
+    }
+
+    #[test]
+    fn deflects_two_thirds() {
+        let self_service = self_service(E * E - 1.0);
+
+        assert_eq!(33, self_service.deflected_quantity(100));
+    }
+
+    #[test]
+    fn respects_max_deflection() {
+        let self_service = SelfService {
+            factor: E - 1.0,
+            max_deflection: 0.5,
+        };
+
+        assert_eq!(75, self_service.deflected_quantity(100));
+    }
+}
@@ -1,20 +1,98 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 
-use super::Attribute;
+use super::{Attribute, AttributeInterner, ConfigError, ContactType};
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+fn default_efficiency() -> f64 {
+    1.0
+}
+
+fn default_acw_factor() -> f64 {
+    1.0
+}
+
+fn default_availability() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Server {
     /// Default is an empty attribute array.
     #[serde(default)]
     pub attributes: Vec<Attribute>,
+    /// Skills only used once a request overflows with `PrimarySkill` routing, otherwise the same
+    /// as `attributes`. Default is an empty attribute array.
+    #[serde(default)]
+    pub secondary_attributes: Vec<Attribute>,
+    /// Default is all channels.
+    #[serde(default = "ContactType::all")]
+    pub channels: Vec<ContactType>,
     pub quantity: usize,
+    /// Default is 1.0.
+    #[serde(default = "default_efficiency")]
+    pub efficiency: f64,
+    /// Attribute name to efficiency, overriding `efficiency` for requests requiring that attribute.
+    /// Default is no overrides.
+    #[serde(default)]
+    pub attribute_efficiency: HashMap<String, f64>,
+    /// Default is 1.0.
+    #[serde(default = "default_acw_factor")]
+    pub acw_factor: f64,
+    /// Default is 1.0, where the server is always present. Must be from 0.0 to 1.0.
+    #[serde(default = "default_availability")]
+    pub availability: f64,
 }
 
-impl From<&Server> for crate::Server {
-    fn from(s: &Server) -> Self {
-        Self {
-            attributes: s.attributes.iter().map(crate::Attribute::from).collect(),
-            ..Self::default()
+/// Errors unless `value` is positive and finite, e.g. a multiplier of handle time.
+fn positive(field: &'static str, value: f64) -> Result<(), ConfigError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::NotPositive(field, value))
+    }
+}
+
+impl Server {
+    /// Errors when a value can't be simulated, e.g. an `efficiency` or `acw_factor` which isn't
+    /// positive, or an `availability` which isn't a fraction.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        positive("efficiency", self.efficiency)?;
+        for efficiency in self.attribute_efficiency.values() {
+            positive("attribute_efficiency", *efficiency)?;
+        }
+        positive("acw_factor", self.acw_factor)?;
+
+        if (0.0..=1.0).contains(&self.availability) {
+            Ok(())
+        } else {
+            Err(ConfigError::NotFraction("availability", self.availability))
+        }
+    }
+
+    /// Converts to a simulation `Server`, interning attribute names with `interner`.
+    pub fn to_simulation(&self, interner: &mut AttributeInterner) -> crate::Server {
+        crate::Server {
+            attributes: self
+                .attributes
+                .iter()
+                .chain(&self.secondary_attributes)
+                .map(|a| a.to_simulation(interner))
+                .collect(),
+            secondary_attributes: self
+                .secondary_attributes
+                .iter()
+                .map(|a| interner.intern(&a.name))
+                .collect(),
+            channels: self.channels.iter().map(crate::ContactType::from).collect(),
+            efficiency: self.efficiency,
+            attribute_efficiency: self
+                .attribute_efficiency
+                .iter()
+                .map(|(name, efficiency)| (interner.intern(name), *efficiency))
+                .collect(),
+            acw_factor: self.acw_factor,
+            availability: self.availability,
+            ..crate::Server::default()
         }
     }
 }
@@ -0,0 +1,72 @@
+use serde::Deserialize;
+
+fn default_step() -> usize {
+    1
+}
+
+/// A staffing sweep varies the quantity of every server group between simulations, e.g. to find
+/// the staffing level where a metric comes on target.
+///
+/// Simulation `i` uses the `i`th staffing level from `start` to `end` (inclusive), wrapping back to
+/// `start` once `end` is passed. Levels which would exceed `end` are not used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct StaffingSweep {
+    /// The server quantity of the first simulation.
+    pub start: usize,
+    /// The largest server quantity to simulate.
+    pub end: usize,
+    /// The increase in server quantity between simulations. Default is 1.
+    #[serde(default = "default_step")]
+    pub step: usize,
+}
+
+impl StaffingSweep {
+    /// The number of distinct staffing levels in the sweep.
+    #[must_use]
+    fn levels(&self) -> usize {
+        self.end.saturating_sub(self.start) / self.step.max(1) + 1
+    }
+
+    /// The server quantity of simulation `run`.
+    #[must_use]
+    pub fn quantity(&self, run: usize) -> usize {
+        self.start + (run % self.levels()) * self.step.max(1)
+    }
+
+    /// The largest server quantity of any simulation.
+    #[must_use]
+    pub fn max_quantity(&self) -> usize {
+        self.quantity(self.levels() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_between_start_and_end() {
+        let sweep = StaffingSweep {
+            start: 10,
+            end: 15,
+            step: 2,
+        };
+
+        let quantities: Vec<usize> = (0..4).map(|run| sweep.quantity(run)).collect();
+
+        assert_eq!(vec![10, 12, 14, 10], quantities);
+        assert_eq!(14, sweep.max_quantity());
+    }
+
+    #[test]
+    fn end_before_start_uses_start() {
+        let sweep = StaffingSweep {
+            start: 5,
+            end: 2,
+            step: 1,
+        };
+
+        assert_eq!(5, sweep.quantity(3));
+        assert_eq!(5, sweep.max_quantity());
+    }
+}
@@ -39,24 +39,28 @@
 // Caused by hermit-abi dependency in rayon and clap
 #![allow(clippy::multiple_crate_versions)]
 
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::sync::mpsc::channel;
-use std::thread::available_parallelism;
+use std::thread::{self, available_parallelism};
 
 use clap::Parser;
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 use rayon::prelude::*;
 
 mod args;
 mod config;
+mod progress;
 
 use args::{log_level, Args};
-use awt_metrics::Aggregator;
+use awt_metrics::{Aggregator, Run, Summary};
 use awt_simulation::{
-    attribute::Attribute, client::Client, error::Error as SimulationError, server::Server,
-    Config as SimulationConfig, Simulation,
+    attribute::Attribute, client::Client, contact_type::ContactType, distribution::Distribution,
+    error::Error as SimulationError, server::Server, Config as SimulationConfig, Simulation,
 };
 
-use config::Config;
+use config::{Config, Parsed};
+use progress::Progress;
 
 fn run_sim(counter: usize, config: SimulationConfig) -> Result<Simulation, SimulationError> {
     let mut sim = Simulation::from(config);
@@ -65,7 +69,7 @@ fn run_sim(counter: usize, config: SimulationConfig) -> Result<Simulation, Simul
     sim.enable()?;
     info!(target: "main", "sim {counter}: enabled");
 
-    while sim.tick() {}
+    while sim.try_tick()? {}
     info!(target: "main", "sim {counter}: finished ticking");
 
     Ok(sim)
@@ -73,8 +77,8 @@ fn run_sim(counter: usize, config: SimulationConfig) -> Result<Simulation, Simul
 
 fn main() {
     match try_main() {
-        Ok(()) => {
-            std::process::exit(exitcode::OK);
+        Ok(code) => {
+            std::process::exit(code);
         }
         Err(err) => {
             error!(target: "main", "{err}");
@@ -83,55 +87,292 @@ fn main() {
     }
 }
 
-fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+fn try_main() -> Result<exitcode::ExitCode, Box<dyn std::error::Error>> {
     let args = Args::parse();
 
     let log_level = log_level(args.log_level);
     simple_logger::init_with_level(log_level)?;
 
-    let config_path = args.config_path.unwrap();
-    let config = Config::try_from(&config_path)?.parsed()?;
+    let config =
+        Config::from_paths(std::iter::once(&args.config_path).chain(&args.configs))?.parsed()?;
 
-    // We want to pin some cores, but not all the cores
-    let sim_threads = available_parallelism()?.get() - 1;
+    let sim_threads = sim_threads(args.threads, available_parallelism()?.get());
     rayon::ThreadPoolBuilder::new()
         .num_threads(sim_threads)
         .build_global()?;
     debug!(target: "main", "setting rayon to use {sim_threads} threads");
 
     trace!(target: "main", "config: {config:?}");
+    let (mut aggregator, runs) = run_simulations(config, |progress| {
+        eprint!("\r{progress}");
+        if progress.percent() >= 100f64 {
+            eprintln!();
+        }
+    })?;
+
+    let summary = write_stats(&mut output(&args)?, &mut aggregator, &runs, &args)?;
+
+    Ok(exit_code(&summary, &args))
+}
+
+/// Loads `run` into `aggregator`, which evaluated it, to report its metrics.
+fn loaded<'a>(aggregator: &'a mut Aggregator, run: &Run) -> &'a Aggregator {
+    aggregator
+        .load(run)
+        .expect("Runs should be evaluated by the same aggregator");
+    aggregator
+}
+
+/// Returns `exitcode::SOFTWARE` when `--fail-on-miss` is set and any metric of the `summary`
+/// missed its target.
+fn exit_code(summary: &Summary, args: &Args) -> exitcode::ExitCode {
+    if args.fail_on_miss && !summary.passed() {
+        exitcode::SOFTWARE
+    } else {
+        exitcode::OK
+    }
+}
+
+/// The number of threads to run simulations on. Requesting more threads than are `available` is
+/// allowed, but warned about.
+fn sim_threads(requested: Option<usize>, available: usize) -> usize {
+    match requested {
+        Some(0) => available,
+        Some(threads) => {
+            if threads > available {
+                warn!(target: "main", "{threads} threads requested, only {available} available");
+            }
+            threads
+        }
+        // We want to pin some cores, but not all the cores
+        None => available - 1,
+    }
+}
+
+/// Run all the simulations described by `config`, returning the `Aggregator` of its metrics with a
+/// `Run` it evaluated for each simulation. `on_progress` is called from a monitoring thread each
+/// time a simulation completes.
+fn run_simulations<F>(
+    config: Parsed,
+    mut on_progress: F,
+) -> Result<(Aggregator, Vec<Run>), SimulationError>
+where
+    F: FnMut(&Progress) + Send + 'static,
+{
     let metrics_aggregator = Aggregator::with_metrics(&config.metrics());
+    let seeds: Vec<u64> = (0..config.simulations()).map(|i| config.seed(i)).collect();
 
-    // Setup notification channel to monitor simulations
+    // Setup notification channel to monitor simulations. This runs outside of the rayon pool so
+    // that it never blocks a worker thread.
     let (sender, reciever) = channel::<usize>();
-    rayon::spawn(move || {
+    let mut progress = Progress::new(config.simulations());
+    let monitor = thread::spawn(move || {
         for simulation in reciever {
-            debug!("Simulation {simulation} complete");
+            progress.complete();
+            debug!("Simulation {simulation} complete, {progress}");
+            on_progress(&progress);
         }
     });
 
-    let stats = config
+    // Each simulation only borrows the aggregator, which is shared between the rayon workers
+    let runs = config
         .into_par_iter()
         .map_with(&sender, |s, (index, config)| {
-            // Simulation config is cloned for each run since these are consumed by each simulation
-            // which is done to ensure data encapsulation. Trade off is memory footprint, which is
-            // rather small for these sims.
-            run_sim(index, config.new_sim(index)).map(|sim| {
-                let mut stats = metrics_aggregator.clone();
-                stats.set_simulation(index);
-                stats.calculate(&sim.request_data());
+            run_sim(index, config).map(|sim| {
+                let mut run = metrics_aggregator.evaluate(&sim).unwrap_or_else(|err| {
+                    warn!(target: "main", "sim {index}: {err}");
+                    Run::default()
+                });
+                run.set_simulation(index);
+                run.set_seed(seeds[index]);
 
                 // notify the channel this simulation is complete
                 s.send(index).unwrap();
-                stats
+                run
             })
         })
-        .collect::<Result<Vec<Aggregator>, SimulationError>>()?;
+        .collect::<Result<Vec<Run>, SimulationError>>()?;
     drop(sender);
+    monitor.join().expect("Simulation monitor should not panic");
+
+    Ok((metrics_aggregator, runs))
+}
+
+/// The destination for results, either the `--output` file or stdout.
+fn output(args: &Args) -> io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout().lock()),
+    })
+}
+
+/// Writes the statistics of each of the `runs`, and their `Summary` when requested, returning the
+/// `Summary`.
+fn write_stats<W: Write>(
+    out: &mut W,
+    aggregator: &mut Aggregator,
+    runs: &[Run],
+    args: &Args,
+) -> io::Result<Summary> {
+    let mut summary = Summary::default();
+    for run in runs {
+        let stat = loaded(aggregator, run);
+        if !args.quiet {
+            writeln!(out, "{stat}")?;
+        }
+        summary.push(stat);
+    }
+
+    if args.summary || args.quiet {
+        writeln!(out, "{summary}")?;
+    }
+
+    out.flush()?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const CONFIG: &str = r#"
+        simulations = 3
+        tick_size = { secs = 1, nanos = 0 }
+        tick_until = { secs = 3600, nanos = 0 }
+        rng_seeds = [1, 2, 3]
+        metrics = [
+          { metric = "AbandonRate", target = 0.5 },
+          { metric = "AnswerCount", target = 6 },
+        ]
+
+        [[clients]]
+        handle_time = { secs = 300, nanos = 0 }
+        abandon_time = { secs = 60, nanos = 0 }
+        quantity = 6
+
+        [[servers]]
+        quantity = 6
+    "#;
+
+    fn parsed(toml: &str) -> Parsed {
+        toml::from_str::<Config>(toml).unwrap().parsed().unwrap()
+    }
+
+    fn args(args: &[&str]) -> Args {
+        Args::parse_from([&["awt", "config.toml"], args].concat())
+    }
 
-    for stat in stats {
-        println!("{stat}");
+    #[test]
+    fn threads_default_to_all_but_one() {
+        assert_eq!(7, sim_threads(args(&[]).threads, 8));
     }
 
-    Ok(())
+    #[test]
+    fn threads_zero_is_all_cores() {
+        assert_eq!(8, sim_threads(args(&["--threads", "0"]).threads, 8));
+    }
+
+    #[test]
+    fn threads_can_be_requested() {
+        assert_eq!(2, sim_threads(args(&["--threads", "2"]).threads, 8));
+        assert_eq!(16, sim_threads(args(&["--threads", "16"]).threads, 8));
+    }
+
+    #[test]
+    fn exit_code_on_target() {
+        let (mut aggregator, runs) = run_simulations(parsed(CONFIG), |_| {}).unwrap();
+        let args = args(&["--quiet", "--fail-on-miss"]);
+        let summary = write_stats(&mut io::sink(), &mut aggregator, &runs, &args).unwrap();
+
+        assert_eq!(exitcode::OK, exit_code(&summary, &args));
+    }
+
+    #[test]
+    fn exit_code_on_miss() {
+        let config = CONFIG.replace("target = 6", "target = 7");
+        let (mut aggregator, runs) = run_simulations(parsed(&config), |_| {}).unwrap();
+        let summary = write_stats(&mut io::sink(), &mut aggregator, &runs, &args(&[])).unwrap();
+
+        assert_eq!(exitcode::OK, exit_code(&summary, &args(&[])));
+        assert_eq!(
+            exitcode::SOFTWARE,
+            exit_code(&summary, &args(&["--fail-on-miss"]))
+        );
+    }
+
+    #[test]
+    fn echoes_generated_seeds() {
+        let config = CONFIG.replace("rng_seeds = [1, 2, 3]", "");
+        let parsed = parsed(&config);
+        let seeds: Vec<u64> = (0..3).map(|i| parsed.seed(i)).collect();
+
+        let (mut aggregator, runs) = run_simulations(parsed, |_| {}).unwrap();
+
+        for run in &runs {
+            let seed = seeds[run.simulation()];
+            assert_eq!(Some(seed), run.seed());
+            let stat = loaded(&mut aggregator, run);
+            assert!(stat.to_string().contains(&format!(", seed: {seed}\n")));
+        }
+    }
+
+    #[test]
+    fn reports_progress() {
+        let (sender, reciever) = channel();
+        run_simulations(parsed(CONFIG), move |progress| {
+            sender.send(progress.completed()).unwrap();
+        })
+        .unwrap();
+
+        assert_eq!(vec![1, 2, 3], reciever.iter().collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn writes_summary() {
+        let (mut aggregator, runs) = run_simulations(parsed(CONFIG), |_| {}).unwrap();
+        let mut out = Vec::new();
+        write_stats(&mut out, &mut aggregator, &runs, &args(&["--summary"])).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Summary of 3 simulations"));
+        assert!(out.contains("AbandonRate              3/3     0\n"));
+        assert!(out.contains("AnswerCount              3/3     6\n"));
+    }
+
+    #[test]
+    fn summary_is_optional() {
+        let (mut aggregator, runs) = run_simulations(parsed(CONFIG), |_| {}).unwrap();
+        let mut out = Vec::new();
+        write_stats(&mut out, &mut aggregator, &runs, &args(&[])).unwrap();
+
+        assert!(!String::from_utf8(out).unwrap().contains("Summary"));
+    }
+
+    #[test]
+    fn quiet_only_writes_summary() {
+        let (mut aggregator, runs) = run_simulations(parsed(CONFIG), |_| {}).unwrap();
+        let mut out = Vec::new();
+        write_stats(&mut out, &mut aggregator, &runs, &args(&["--quiet"])).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("Summary of 3 simulations"));
+        assert!(!out.contains("Statistics for"));
+    }
+
+    #[test]
+    fn writes_to_output_file() {
+        let path = std::env::temp_dir().join(format!("awt-output-{}.txt", std::process::id()));
+        let path_arg = path.to_str().unwrap();
+        let args = args(&["--quiet", "--output", path_arg]);
+
+        let (mut aggregator, runs) = run_simulations(parsed(CONFIG), |_| {}).unwrap();
+        write_stats(&mut output(&args).unwrap(), &mut aggregator, &runs, &args).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(contents.starts_with("Summary of 3 simulations"));
+        assert_eq!(4, contents.lines().count());
+    }
 }
@@ -0,0 +1,66 @@
+use core::fmt::{Display, Formatter, Result};
+
+/// Tracks how many of the total simulations have completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    completed: usize,
+    total: usize,
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(
+            f,
+            "{}/{} simulations complete ({:.0}%)",
+            self.completed,
+            self.total,
+            self.percent()
+        )
+    }
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            completed: 0,
+            total,
+        }
+    }
+
+    /// Mark a single simulation as complete.
+    pub fn complete(&mut self) {
+        self.completed += 1;
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    /// The percentage of simulations completed. An empty run is always 100% complete.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100f64;
+        }
+        self.completed as f64 / self.total as f64 * 100f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_completion() {
+        let mut progress = Progress::new(4);
+        progress.complete();
+
+        assert_eq!(1, progress.completed());
+        assert_eq!("1/4 simulations complete (25%)", format!("{progress}"));
+    }
+
+    #[test]
+    fn empty_is_complete() {
+        assert!((Progress::new(0).percent() - 100f64).abs() < f64::EPSILON);
+    }
+}